@@ -3,7 +3,7 @@ use std::io;
 use ddp_rs::connection;
 use ddp_rs::protocol;
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style, Stylize},
@@ -15,12 +15,25 @@ use ratatui::{
 
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, RwLock,
+    Arc, Mutex, RwLock,
 };
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use crate::frame_file::FrameRecorder;
 use crate::led_controller::PixelController;
+use crate::output::OutputSettings;
+use crate::pipeline::{self, FrameBuffer, RenderedFrame, StandbySettings};
+use crate::power::PowerModel;
+use crate::scheduler::{self, FrameScheduler};
+use crate::tempo;
+
+/// Set from the signal handler to ask the app to shut down cleanly
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
 
 #[derive(PartialEq)]
 enum CurrentScreen {
@@ -29,12 +42,21 @@ enum CurrentScreen {
 }
 
 pub struct App {
-    conn: Arc<RwLock<connection::DDPConnection>>,
+    /// Handed to the pipeline thread when it starts, which then owns it
+    conn: Option<connection::DDPConnection>,
     controller: Arc<RwLock<PixelController>>,
+    frames: Arc<FrameBuffer>,
     thread_alive: Arc<AtomicBool>,
-    transmit_handle: Option<thread::JoinHandle<()>>,
-    controller_handle: Option<thread::JoinHandle<()>>,
+    pipeline_handle: Option<thread::JoinHandle<()>>,
+    /// Frames are added by the pipeline thread while this holds a recorder
+    recorder: Arc<Mutex<Option<FrameRecorder>>>,
+    /// Why the last recording couldn't be started or saved, which the pipeline
+    /// thread also sets if writing a frame fails
+    recording_error: Arc<Mutex<Option<String>>>,
     enabled: Arc<AtomicBool>,
+    /// Set when a key may have changed the controller, so the pipeline thread
+    /// knows to publish it again while nothing is being rendered in standby
+    edited: Arc<AtomicBool>,
     update_ms: u64,
     current_screen: CurrentScreen,
     exit: bool,
@@ -42,25 +64,34 @@ pub struct App {
 
 impl App {
     pub fn new(ip: &str, pixel_count: usize, update_ms: u64) -> App {
-        let conn = Arc::new(RwLock::new(
-            connection::DDPConnection::try_new(
-                ip,
-                protocol::PixelConfig::default(),
-                protocol::ID::Default,
-                std::net::UdpSocket::bind("0.0.0.0:4048").unwrap(),
-            )
-            .unwrap(),
-        ));
+        let output = OutputSettings::load(ip);
+        let conn = connection::DDPConnection::try_new(
+            ip,
+            output.ddp_pixel_config(),
+            protocol::ID::Default,
+            std::net::UdpSocket::bind("0.0.0.0:4048").unwrap(),
+        )
+        .unwrap();
+
+        install_panic_hook(
+            ip.to_string(),
+            output.ddp_pixel_config(),
+            pixel_count * output.channels(),
+        );
 
-        let controller = Arc::new(RwLock::new(PixelController::new(pixel_count)));
+        let mut controller = PixelController::new(pixel_count, output, PowerModel::load(ip));
+        let frames = Arc::new(pipeline::new_frame_buffer(controller.render_blank()));
 
         App {
-            conn,
-            controller,
+            conn: Some(conn),
+            controller: Arc::new(RwLock::new(controller)),
+            frames,
             thread_alive: Arc::new(AtomicBool::new(false)),
-            transmit_handle: None,
-            controller_handle: None,
+            pipeline_handle: None,
+            recorder: Arc::new(Mutex::new(None)),
+            recording_error: Arc::new(Mutex::new(None)),
             enabled: Arc::new(AtomicBool::new(true)),
+            edited: Arc::new(AtomicBool::new(false)),
             update_ms,
             current_screen: CurrentScreen::MainView,
             exit: false,
@@ -68,11 +99,16 @@ impl App {
     }
 
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
-        self.start_transmit_thread();
+        self.start_pipeline_thread();
         let tick_rate = Duration::from_millis(self.update_ms);
         let mut last_tick = Instant::now();
 
         while !self.exit {
+            if SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
+                self.exit();
+                break;
+            }
+
             terminal.draw(|frame| self.draw(frame))?;
 
             let timeout = tick_rate.saturating_sub(last_tick.elapsed());
@@ -85,23 +121,33 @@ impl App {
         Ok(())
     }
 
+    /// Draws from the last rendered frame rather than the controller, so drawing
+    /// never waits on the pipeline thread
     fn draw(&self, frame: &mut Frame) {
+        let rendered = self.frames.load();
+
         let display = Layout::default()
-            .constraints([Constraint::Length(5), Constraint::Min(1)])
+            .constraints([
+                Constraint::Length(5),
+                Constraint::Min(1),
+                Constraint::Length(3),
+            ])
             .split(frame.area());
 
         let header = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Percentage(25),
-                Constraint::Percentage(50),
-                Constraint::Percentage(25),
+                Constraint::Percentage(35),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
             ])
             .split(display[0]);
 
-        self.draw_effect(frame, header[0]);
+        self.draw_effect(frame, header[0], &rendered);
         self.draw_title(frame, header[1]);
-        self.draw_brightness(frame, header[2]);
+        self.draw_tempo(frame, header[2]);
+        self.draw_brightness(frame, header[3], &rendered);
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -110,22 +156,17 @@ impl App {
 
         frame.render_widget(block, display[1]);
 
-        {
-            let controller = self.controller.read().unwrap();
-            let current_effect = controller.get_current_effect();
+        rendered.effect.draw(frame, display[1]);
 
-            current_effect.draw(frame, display[1]);
-        }
+        self.draw_stats(frame, display[2]);
 
         if self.current_screen == CurrentScreen::Exiting {
             self.draw_exit(frame, display[1]);
         }
     }
 
-    fn draw_effect(&self, frame: &mut Frame, layout: Rect) {
-        let controller = self.controller.read().unwrap();
-        let current_effect = controller.get_current_effect();
-        let current_effect_str = current_effect.to_string();
+    fn draw_effect(&self, frame: &mut Frame, layout: Rect, rendered: &RenderedFrame) {
+        let current_effect_str = rendered.effect.to_string();
 
         let effect_block = Block::default()
             .borders(Borders::ALL)
@@ -169,19 +210,32 @@ impl App {
                 Style::default().fg(Color::Green),
             )),
             Line::from(Span::raw("")),
-            if self.enabled.load(Ordering::SeqCst) {
-                Line::from(Span::styled(
-                    "(e) Enabled ",
-                    Style::default().fg(Color::Green),
-                ))
-                .centered()
-            } else {
-                Line::from(Span::styled(
-                    "(e) Disabled",
-                    Style::default().fg(Color::Red),
-                ))
-                .centered()
-            },
+            Line::from(vec![
+                if self.enabled.load(Ordering::SeqCst) {
+                    Span::styled("(e) Enabled ", Style::default().fg(Color::Green))
+                } else {
+                    Span::styled("(e) Disabled", Style::default().fg(Color::Red))
+                },
+                Span::raw("   "),
+                match self.recorder.lock().unwrap().as_ref() {
+                    Some(recorder) => Span::styled(
+                        format!(
+                            "(o) Recording {} {:.0}s",
+                            recorder.path(),
+                            recorder.elapsed()
+                        ),
+                        Style::default().fg(Color::Red),
+                    ),
+                    None => match self.recording_error.lock().unwrap().as_ref() {
+                        Some(err) => Span::styled(
+                            format!("(o) Record: {}", err),
+                            Style::default().fg(Color::Red),
+                        ),
+                        None => Span::styled("(o) Record", Style::default().fg(Color::White)),
+                    },
+                },
+            ])
+            .centered(),
         ])
         .bold()
         .centered()
@@ -190,27 +244,48 @@ impl App {
         frame.render_widget(title, layout);
     }
 
-    fn draw_brightness(&self, frame: &mut Frame, layout: Rect) {
-        let controller = self.controller.read().unwrap();
-
+    fn draw_brightness(&self, frame: &mut Frame, layout: Rect, rendered: &RenderedFrame) {
         let brightness_block = Block::default()
             .borders(Borders::ALL)
             .border_set(border::THICK)
             .style(Style::default());
 
+        let report = rendered.power;
+        let supply = if report.supply_amps > 0. {
+            format!(" / {:.1}A", report.supply_amps)
+        } else {
+            String::new()
+        };
+        let power = if report.is_limited() {
+            Span::styled(
+                format!(
+                    "{:.2}A{} ({:.0}%)",
+                    report.milliamps / 1000.,
+                    supply,
+                    report.scale * 100.
+                ),
+                Style::default().fg(Color::Red),
+            )
+        } else {
+            Span::styled(
+                format!("{:.2}A{}", report.milliamps / 1000., supply),
+                Style::default().fg(Color::White),
+            )
+        };
+
         let brightness_text = vec![
             Line::from(Span::styled(
                 "Brightness",
                 Style::default().fg(Color::White),
             )),
-            Line::from(format!("{:.2}", controller.get_brightness())),
             Line::from(vec![
                 Span::raw("<"),
-                Span::styled("-", Style::default().fg(Color::Red)),
-                Span::raw("  "),
-                Span::styled("+", Style::default().fg(Color::Green)),
+                Span::styled("- ", Style::default().fg(Color::Red)),
+                Span::raw(format!("{:.2}", rendered.brightness)),
+                Span::styled(" +", Style::default().fg(Color::Green)),
                 Span::raw(">"),
             ]),
+            Line::from(power),
         ];
 
         let brightness = Paragraph::new(brightness_text)
@@ -220,6 +295,71 @@ impl App {
         frame.render_widget(brightness, layout);
     }
 
+    fn draw_tempo(&self, frame: &mut Frame, layout: Rect) {
+        let tempo_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::THICK)
+            .style(Style::default());
+
+        let (bar, beat) = tempo::bar_position();
+        let mut beat_markers = vec![Span::styled("(t) ", Style::default().fg(Color::Green))];
+        for i in 0..tempo::beats_per_bar() {
+            beat_markers.push(if i == beat {
+                Span::styled("●", Style::default().fg(Color::Green))
+            } else {
+                Span::styled("○", Style::default().fg(Color::White))
+            });
+        }
+
+        let tempo_text = vec![
+            Line::from(vec![
+                Span::styled("[ ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("{:3.1} BPM", tempo::bpm()),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" ]", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(format!("Bar {}", bar + 1)),
+            Line::from(beat_markers),
+        ];
+
+        let tempo = Paragraph::new(tempo_text).centered().block(tempo_block);
+
+        frame.render_widget(tempo, layout);
+    }
+
+    fn draw_stats(&self, frame: &mut Frame, layout: Rect) {
+        let stats = scheduler::stats();
+
+        let stats_block = Block::default()
+            .borders(Borders::ALL)
+            .border_set(border::THICK)
+            .style(Style::default());
+
+        // Highlight the frame rate once it falls noticeably behind the target
+        let fps_colour = if stats.fps < stats.target_fps * 0.95 {
+            Color::Red
+        } else {
+            Color::Green
+        };
+
+        let stats_text = Line::from(vec![
+            Span::styled(
+                format!("FPS: {:4.1}", stats.fps),
+                Style::default().fg(fps_colour),
+            ),
+            Span::raw(format!(" / {:2.0}", stats.target_fps)),
+            Span::raw(format!("   Render: {:5.2}ms", stats.render_ms)),
+            Span::raw(format!("   Transmit: {:5.2}ms", stats.transmit_ms)),
+            Span::raw(format!("   Jitter: {:5.2}ms", stats.jitter_ms)),
+        ]);
+
+        let stats = Paragraph::new(stats_text).centered().block(stats_block);
+
+        frame.render_widget(stats, layout);
+    }
+
     fn draw_exit(&self, frame: &mut Frame, layout: Rect) {
         let percent_x = 40;
         let percent_y = 30;
@@ -270,11 +410,26 @@ impl App {
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        // The terminal is in raw mode so Ctrl+C arrives as a key rather than a signal
+        if key_event.modifiers.contains(KeyModifiers::CONTROL)
+            && key_event.code == KeyCode::Char('c')
+        {
+            self.exit();
+            return;
+        }
+
         match self.current_screen {
             CurrentScreen::Exiting => match key_event.code {
                 KeyCode::Char('q') | KeyCode::Char('y') | KeyCode::Char('Y') => self.exit(),
                 _ => self.current_screen = CurrentScreen::MainView,
             },
+            CurrentScreen::MainView if self.effect_captures_input() => {
+                self.controller
+                    .write()
+                    .unwrap()
+                    .get_current_effect_mut()
+                    .handle_input(key_event);
+            }
             CurrentScreen::MainView => match key_event.code {
                 KeyCode::Esc | KeyCode::Char('q') => self.current_screen = CurrentScreen::Exiting,
                 KeyCode::Left => {
@@ -293,6 +448,12 @@ impl App {
                     let mut controller = self.controller.write().unwrap();
                     controller.decrease_brightness();
                 }
+                KeyCode::Char('t') => tempo::tap(),
+                KeyCode::Char('[') => tempo::set_bpm(tempo::bpm() - 1.),
+                KeyCode::Char(']') => tempo::set_bpm(tempo::bpm() + 1.),
+                KeyCode::Char('{') => tempo::set_bpm(tempo::bpm() - 10.),
+                KeyCode::Char('}') => tempo::set_bpm(tempo::bpm() + 10.),
+                KeyCode::Char('o') => self.toggle_recording(),
                 KeyCode::Char('e') => {
                     let new_enabled = !self.enabled.load(Ordering::SeqCst);
                     self.enabled.store(new_enabled, Ordering::SeqCst);
@@ -306,83 +467,208 @@ impl App {
                 }
             },
         }
-    }
 
-    fn start_transmit_thread(&mut self) {
-        self.thread_alive.store(true, Ordering::SeqCst);
+        // Only after the change is made, so it can't be published half done
+        self.edited.store(true, Ordering::SeqCst);
+    }
 
-        let transmit_alive = self.thread_alive.clone();
-        let transmit_controller = self.controller.clone();
-        let transmit_conn = self.conn.clone();
-        let transmit_enabled = self.enabled.clone();
+    fn effect_captures_input(&self) -> bool {
+        self.controller
+            .read()
+            .unwrap()
+            .get_current_effect()
+            .captures_input()
+    }
 
-        let transmit_ms = self.update_ms;
+    /// Updates the effect, renders a frame and sends it, all on one thread at the
+    /// frame rate. Each frame is published to `frames` before it is sent so the
+    /// interface always shows what is on the LEDs. The controller is only locked
+    /// while the frame is made, recording and sending happen after it is released.
+    /// While disabled the output fades out and then only sends black as a keep alive
+    fn start_pipeline_thread(&mut self) {
+        self.thread_alive.store(true, Ordering::SeqCst);
 
-        self.transmit_handle = Some(thread::spawn(move || {
-            let tick_rate = Duration::from_millis(transmit_ms);
-            let mut last_tick = Instant::now();
-            while transmit_alive.load(Ordering::SeqCst) {
-                {
-                    if last_tick.elapsed() >= tick_rate {
-                        if !transmit_enabled.load(Ordering::SeqCst) {
+        let alive = self.thread_alive.clone();
+        let controller = self.controller.clone();
+        let frames = self.frames.clone();
+        let enabled = self.enabled.clone();
+        let edited = self.edited.clone();
+        let recorder = self.recorder.clone();
+        let recording_error = self.recording_error.clone();
+        let mut conn = self.conn.take().expect("Pipeline thread already started");
+        let frame_ms = self.update_ms;
+        let standby = StandbySettings::load();
+
+        self.pipeline_handle = Some(thread::spawn(move || {
+            let mut scheduler = FrameScheduler::new(Duration::from_millis(frame_ms));
+            // How far faded in the output is, 0 when in standby
+            let mut level: f32 = 1.;
+            let mut standby_sent: Option<Instant> = None;
+
+            while alive.load(Ordering::SeqCst) {
+                let delta = scheduler.wait();
+                let is_enabled = enabled.load(Ordering::SeqCst);
+                level = standby.fade(level, is_enabled, delta.as_secs_f32());
+
+                let start = Instant::now();
+                let recording = recorder.lock().unwrap().is_some();
+                let (frame, colours) = if is_enabled || level > 0. {
+                    standby_sent = None;
+
+                    let mut controller = controller.write().unwrap();
+                    controller.update(delta.as_secs_f32());
+                    let colours = recording.then(|| controller.pixel_bytes(level));
+                    (Arc::new(controller.render_frame(level)), colours)
+                } else {
+                    // Fully faded out, only black is sent from here on and
+                    // only as often as the keep alive needs
+                    let due = match standby_sent {
+                        None => true,
+                        Some(sent) => {
+                            standby.keep_alive_seconds > 0.
+                                && sent.elapsed().as_secs_f32() >= standby.keep_alive_seconds
+                        }
+                    };
+                    if !due {
+                        // Nothing is sent, but the TUI still follows changes made
+                        // while in standby
+                        if !edited.swap(false, Ordering::SeqCst) {
                             continue;
                         }
-                        let controller = transmit_controller.read().unwrap();
-                        let mut connection = transmit_conn.write().unwrap();
-
-                        controller.transmit(&mut connection);
 
-                        last_tick = Instant::now();
+                        let controller = controller.read().unwrap();
+                        let last = frames.load_full();
+                        frames.store(Arc::new(RenderedFrame {
+                            data: last.data.clone(),
+                            power: last.power,
+                            effect: controller.get_current_effect().clone(),
+                            brightness: controller.get_brightness(),
+                        }));
+                        continue;
                     }
+                    standby_sent = Some(Instant::now());
+
+                    let mut controller = controller.write().unwrap();
+                    let colours = recording.then(|| controller.pixel_bytes(0.));
+                    (Arc::new(controller.render_blank()), colours)
+                };
+                if let Some(colours) = colours {
+                    record_frame(&recorder, &recording_error, &colours, &frame.data);
+                }
+                frames.store(frame.clone());
+                scheduler::record_render(start.elapsed());
+
+                let start = Instant::now();
+                pipeline::send(&frame, &mut conn);
+                scheduler::record_transmit(start.elapsed());
+                // Keep alive frames are deliberately sparse, so they'd only drag the rate down
+                if standby_sent.is_none() {
+                    scheduler::record_frame(delta, scheduler.period());
                 }
-                thread::sleep(std::time::Duration::from_millis(transmit_ms));
             }
-        }));
 
-        let controller_alive = self.thread_alive.clone();
-        let controller = self.controller.clone();
-        let update_enabled = self.enabled.clone();
-        let update_time = self.update_ms;
+            // Fade out and leave the LEDs dark rather than frozen on the last frame
+            while level > 0. {
+                let delta = scheduler.wait();
+                level = standby.fade(level, false, delta.as_secs_f32());
+
+                let frame = {
+                    let mut controller = controller.write().unwrap();
+                    controller.update(delta.as_secs_f32());
+                    controller.render_frame(level)
+                };
+                pipeline::send(&frame, &mut conn);
+            }
 
-        self.controller_handle = Some(thread::spawn(move || {
-            let tick_rate = Duration::from_millis(update_time);
-            let mut last_tick = Instant::now();
+            let frame = controller.write().unwrap().render_blank();
+            pipeline::send(&frame, &mut conn);
+        }));
+    }
 
-            while controller_alive.load(Ordering::SeqCst) {
-                {
-                    if last_tick.elapsed() >= tick_rate {
-                        if !update_enabled.load(Ordering::SeqCst) {
-                            continue;
-                        }
-                        let mut controller = controller.write().unwrap();
+    /// Starts recording what is sent to a new file named after the current time,
+    /// or finishes the recording if one is running
+    fn toggle_recording(&mut self) {
+        let mut recorder = self.recorder.lock().unwrap();
 
-                        controller.update((update_time as f32) / 1000.);
+        if let Some(running) = recorder.take() {
+            if let Err(err) = running.finish() {
+                *self.recording_error.lock().unwrap() =
+                    Some(format!("Couldn't save the recording: {}", err));
+            }
+            return;
+        }
 
-                        last_tick = Instant::now();
-                    }
-                }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = format!("recording_{}.ledf", seconds);
+        let (pixel_count, channels) = {
+            let controller = self.controller.read().unwrap();
+            (controller.get_num_pixels(), controller.get_channels())
+        };
+        let fps = 1000. / self.update_ms as f32;
 
-                thread::sleep(std::time::Duration::from_millis(update_time));
+        match FrameRecorder::create(&path, pixel_count, channels, fps) {
+            Ok(running) => {
+                *recorder = Some(running);
+                *self.recording_error.lock().unwrap() = None;
             }
-        }));
+            Err(err) => {
+                *self.recording_error.lock().unwrap() =
+                    Some(format!("Couldn't create {}: {}", path, err));
+            }
+        }
     }
 
     fn exit(&mut self) {
         self.thread_alive.store(false, Ordering::SeqCst);
-        self.transmit_handle
+        self.pipeline_handle
             .take()
             .expect("Called stop on non_running thread")
             .join()
             .expect("Could not join spawned thread");
 
-        self.controller_handle
-            .take()
-            .expect("Called stop on non_running thread")
-            .join()
-            .expect("Could not join spawned thread");
+        if let Some(recorder) = self.recorder.lock().unwrap().take() {
+            let _ = recorder.finish();
+        }
 
         self.exit = true;
 
         self.controller.read().unwrap().save_settings();
     }
 }
+
+/// Adds a frame's RGB `colours` and the `sent` bytes to the recording if one is
+/// running. Recording stops if the file can't be written rather than interrupting
+/// the output
+/// Adds a frame to the recording, stopping it and keeping the reason to show if
+/// the frame can't be written
+fn record_frame(
+    recorder: &Mutex<Option<FrameRecorder>>,
+    error: &Mutex<Option<String>>,
+    colours: &[u8],
+    sent: &[u8],
+) {
+    let mut recorder = recorder.lock().unwrap();
+    if let Some(running) = recorder.as_mut() {
+        if let Err(err) = running.record(colours, sent) {
+            *error.lock().unwrap() = Some(format!(
+                "Stopped, couldn't write to {}: {}",
+                running.path(),
+                err
+            ));
+            *recorder = None;
+        }
+    }
+}
+
+/// Puts the terminal back and tries to blank the LEDs before the panic message
+/// is printed, so a crash doesn't leave either in a mess
+fn install_panic_hook(destination: String, pixel_config: protocol::PixelConfig, bytes: usize) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        ratatui::restore();
+        pipeline::blank(&destination, pixel_config, bytes);
+        previous(info);
+    }));
+}
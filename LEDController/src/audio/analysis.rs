@@ -0,0 +1,143 @@
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::audio::{AudioFeatures, NUM_BANDS};
+
+pub const FFT_SIZE: usize = 1024;
+pub const HOP_SIZE: usize = FFT_SIZE / 2;
+
+const MIN_FREQUENCY: f32 = 40.;
+const MAX_FREQUENCY: f32 = 16000.;
+
+const BEAT_HISTORY_SECONDS: f32 = 1.;
+const BEAT_THRESHOLD: f32 = 1.4;
+const BEAT_MIN_INTERVAL: f32 = 0.25;
+const BEAT_PULSE_DECAY: f32 = 4.;
+
+const PEAK_DECAY: f32 = 0.998;
+const MIN_PEAK: f32 = 1e-4;
+
+/// Turns a mono sample stream into per band energies and beats.
+/// Samples are pushed in hops of `HOP_SIZE` with a window of `FFT_SIZE`
+pub struct AudioAnalyser {
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    samples: Vec<f32>,
+    band_edges: [usize; NUM_BANDS + 1],
+    band_peaks: [f32; NUM_BANDS],
+    bass_history: VecDeque<f32>,
+    history_length: usize,
+    hop_seconds: f32,
+    since_beat: f32,
+    features: AudioFeatures,
+}
+
+impl AudioAnalyser {
+    pub fn new(sample_rate: u32) -> AudioAnalyser {
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+
+        // Hann window to reduce leakage between bins
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * f32::cos(2. * std::f32::consts::PI * i as f32 / FFT_SIZE as f32))
+            .collect();
+
+        // Bands are spaced logarithmically, which is roughly how we hear pitch. Low
+        // sample rates can't reach the top bands, so they stop at the Nyquist frequency
+        let bin_width = sample_rate as f32 / FFT_SIZE as f32;
+        let max_frequency = MAX_FREQUENCY.min(sample_rate as f32 / 2.);
+        let mut band_edges = [0; NUM_BANDS + 1];
+        for (i, edge) in band_edges.iter_mut().enumerate() {
+            let frequency =
+                MIN_FREQUENCY * (max_frequency / MIN_FREQUENCY).powf(i as f32 / NUM_BANDS as f32);
+            *edge = ((frequency / bin_width).round() as usize).clamp(1, FFT_SIZE / 2);
+        }
+        // Every band gets at least one bin where there are enough to go round, any
+        // left without one stay empty and read as silent
+        for i in 1..band_edges.len() {
+            band_edges[i] = (band_edges[i - 1] + 1).max(band_edges[i]).min(FFT_SIZE / 2);
+        }
+
+        let hop_seconds = HOP_SIZE as f32 / sample_rate as f32;
+
+        AudioAnalyser {
+            fft,
+            window,
+            samples: vec![0.; FFT_SIZE],
+            band_edges,
+            band_peaks: [MIN_PEAK; NUM_BANDS],
+            bass_history: VecDeque::new(),
+            history_length: (BEAT_HISTORY_SECONDS / hop_seconds).ceil() as usize,
+            hop_seconds,
+            since_beat: BEAT_MIN_INTERVAL,
+            features: AudioFeatures::new(),
+        }
+    }
+
+    pub fn features(&self) -> AudioFeatures {
+        self.features
+    }
+
+    pub fn process(&mut self, hop: &[f32]) {
+        self.samples.drain(..hop.len().min(self.samples.len()));
+        self.samples.extend_from_slice(hop);
+
+        let mut buffer: Vec<Complex<f32>> = self
+            .samples
+            .iter()
+            .zip(self.window.iter())
+            .map(|(s, w)| Complex::new(s * w, 0.))
+            .collect();
+        self.fft.process(&mut buffer);
+
+        let magnitudes: Vec<f32> = buffer[..FFT_SIZE / 2]
+            .iter()
+            .map(|c| c.norm() / FFT_SIZE as f32)
+            .collect();
+
+        // Each band is normalised against its own slowly decaying peak so quiet
+        // and loud tracks both use the full range
+        for band in 0..NUM_BANDS {
+            let bins = &magnitudes[self.band_edges[band]..self.band_edges[band + 1]];
+            let energy = bins.iter().sum::<f32>() / bins.len().max(1) as f32;
+
+            self.band_peaks[band] =
+                f32::max(self.band_peaks[band] * PEAK_DECAY, energy).max(MIN_PEAK);
+            self.features.bands[band] = (energy / self.band_peaks[band]).clamp(0., 1.);
+        }
+
+        let rms = f32::sqrt(hop.iter().map(|s| s * s).sum::<f32>() / hop.len().max(1) as f32);
+        self.features.level = (rms * std::f32::consts::SQRT_2).clamp(0., 1.);
+
+        self.detect_beat(self.features.bands[0] + self.features.bands[1]);
+    }
+
+    fn detect_beat(&mut self, bass: f32) {
+        self.since_beat += self.hop_seconds;
+
+        let average = if self.bass_history.is_empty() {
+            0.
+        } else {
+            self.bass_history.iter().sum::<f32>() / self.bass_history.len() as f32
+        };
+
+        let is_beat = self.bass_history.len() == self.history_length
+            && bass > average * BEAT_THRESHOLD
+            && self.since_beat >= BEAT_MIN_INTERVAL;
+
+        if is_beat {
+            self.since_beat = 0.;
+            self.features.beat_count += 1;
+            self.features.beat_pulse = 1.;
+        } else {
+            self.features.beat_pulse =
+                (self.features.beat_pulse - BEAT_PULSE_DECAY * self.hop_seconds).max(0.);
+        }
+
+        self.bass_history.push_back(bass);
+        if self.bass_history.len() > self.history_length {
+            self.bass_history.pop_front();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+pub mod analysis;
+pub mod source;
+
+use ini::Ini;
+
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audio::analysis::{AudioAnalyser, HOP_SIZE};
+use crate::audio::source::AudioSource;
+use crate::effect::constants::CONFIG_NAME;
+
+pub const NUM_BANDS: usize = 8;
+
+/// The latest analysis of the audio input, shared with every effect
+#[derive(Copy, Clone, Debug)]
+pub struct AudioFeatures {
+    /// Energy per band from bass to treble, normalised to 0 - 1
+    pub bands: [f32; NUM_BANDS],
+    /// Overall loudness, 0 - 1
+    pub level: f32,
+    /// Increments on every detected beat
+    pub beat_count: u64,
+    /// Jumps to 1 on a beat and decays back to 0
+    pub beat_pulse: f32,
+    /// Seconds into the input, only known for WAV files
+    pub position: Option<f64>,
+    pub active: bool,
+}
+
+impl AudioFeatures {
+    pub const fn new() -> AudioFeatures {
+        AudioFeatures {
+            bands: [0.; NUM_BANDS],
+            level: 0.,
+            beat_count: 0,
+            beat_pulse: 0.,
+            position: None,
+            active: false,
+        }
+    }
+}
+
+impl Default for AudioFeatures {
+    fn default() -> AudioFeatures {
+        AudioFeatures::new()
+    }
+}
+
+static FEATURES: RwLock<AudioFeatures> = RwLock::new(AudioFeatures::new());
+
+pub fn features() -> AudioFeatures {
+    *FEATURES.read().unwrap()
+}
+
+/// Opens the source configured in the [Audio] section of the config and starts
+/// analysing it in the background. Does nothing if no source is configured
+pub fn start_from_settings() {
+    let Ok(config) = Ini::load_from_file(CONFIG_NAME) else {
+        return;
+    };
+    let Some(section) = config.section(Some("Audio")) else {
+        return;
+    };
+
+    let channels: usize = section.get("channels").map_or(2, |c| c.parse().unwrap());
+    let sample_rate: u32 = section
+        .get("sample_rate")
+        .map_or(44100, |r| r.parse().unwrap());
+    let looping: bool = section.get("loop").is_none_or(|l| l.parse().unwrap());
+    let path = section.get("path").unwrap_or("");
+
+    if channels == 0 {
+        panic!("The [Audio] channels setting must be at least 1");
+    }
+    if sample_rate == 0 {
+        panic!("The [Audio] sample_rate setting must be above 0");
+    }
+
+    let source = match section.get("source").unwrap_or("none") {
+        "stdin" => AudioSource::stdin(channels, sample_rate),
+        "pipe" => match AudioSource::pipe(path, channels, sample_rate) {
+            Err(err) => panic!("Couldn't open audio pipe {}: {}", path, err),
+            Ok(source) => source,
+        },
+        "wav" => match AudioSource::wav(path, looping) {
+            Err(err) => panic!("Couldn't open audio file {}: {}", path, err),
+            Ok(source) => source,
+        },
+        _ => return,
+    };
+
+    start(source);
+}
+
+pub fn start(mut source: AudioSource) {
+    thread::spawn(move || {
+        let mut analyser = AudioAnalyser::new(source.sample_rate());
+        let mut hop: Vec<f32> = Vec::with_capacity(HOP_SIZE);
+
+        let hop_duration = Duration::from_secs_f64(HOP_SIZE as f64 / source.sample_rate() as f64);
+        let mut next_hop = Instant::now();
+
+        while source.read_mono(HOP_SIZE, &mut hop) {
+            analyser.process(&hop);
+
+            {
+                let mut features = FEATURES.write().unwrap();
+                *features = analyser.features();
+                features.position = source.position();
+                features.active = true;
+            }
+
+            if source.needs_pacing() {
+                next_hop += hop_duration;
+                thread::sleep(next_hop.saturating_duration_since(Instant::now()));
+            }
+        }
+
+        *FEATURES.write().unwrap() = AudioFeatures::new();
+    });
+}
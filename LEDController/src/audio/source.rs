@@ -0,0 +1,147 @@
+use hound::{SampleFormat, WavReader};
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+
+/// A stream of PCM audio. Raw streams are expected to be signed 16 bit little
+/// endian interleaved samples, as produced by `arecord -f S16_LE` or
+/// `ffmpeg -f s16le`
+pub enum AudioSource {
+    Raw {
+        reader: Box<dyn Read + Send>,
+        channels: usize,
+        sample_rate: u32,
+    },
+    Wav {
+        reader: WavReader<BufReader<File>>,
+        looping: bool,
+        frames_read: u64,
+    },
+}
+
+impl AudioSource {
+    pub fn stdin(channels: usize, sample_rate: u32) -> AudioSource {
+        AudioSource::Raw {
+            reader: Box::new(io::stdin()),
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Opening a named pipe blocks until something starts writing to it
+    pub fn pipe(path: &str, channels: usize, sample_rate: u32) -> io::Result<AudioSource> {
+        Ok(AudioSource::Raw {
+            reader: Box::new(BufReader::new(File::open(path)?)),
+            channels,
+            sample_rate,
+        })
+    }
+
+    pub fn wav(path: &str, looping: bool) -> Result<AudioSource, hound::Error> {
+        Ok(AudioSource::Wav {
+            reader: WavReader::open(path)?,
+            looping,
+            frames_read: 0,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        match self {
+            AudioSource::Raw { sample_rate, .. } => *sample_rate,
+            AudioSource::Wav { reader, .. } => reader.spec().sample_rate,
+        }
+    }
+
+    /// Files are read faster than real time, so the caller has to pace them
+    pub fn needs_pacing(&self) -> bool {
+        matches!(self, AudioSource::Wav { .. })
+    }
+
+    /// Reads `count` samples, mixed down to mono in the range -1 to 1.
+    /// Returns false once the stream has ended
+    pub fn read_mono(&mut self, count: usize, out: &mut Vec<f32>) -> bool {
+        out.clear();
+
+        match self {
+            AudioSource::Raw {
+                reader, channels, ..
+            } => {
+                let mut buffer = vec![0u8; count * *channels * 2];
+                if reader.read_exact(&mut buffer).is_err() {
+                    return false;
+                }
+
+                for frame in buffer.chunks_exact(*channels * 2) {
+                    let sum: f32 = frame
+                        .chunks_exact(2)
+                        .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / i16::MAX as f32)
+                        .sum();
+                    out.push(sum / *channels as f32);
+                }
+                true
+            }
+            AudioSource::Wav {
+                reader,
+                looping,
+                frames_read,
+            } => {
+                let spec = reader.spec();
+                let channels = spec.channels as usize;
+                let mut interleaved: Vec<f32> = Vec::with_capacity(count * channels);
+                let mut loop_start = 0;
+
+                while interleaved.len() < count * channels {
+                    let remaining = count * channels - interleaved.len();
+                    let before = interleaved.len();
+
+                    match spec.sample_format {
+                        SampleFormat::Float => interleaved.extend(
+                            reader
+                                .samples::<f32>()
+                                .take(remaining)
+                                .map(|s| s.unwrap_or(0.)),
+                        ),
+                        SampleFormat::Int => {
+                            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                            interleaved.extend(
+                                reader
+                                    .samples::<i32>()
+                                    .take(remaining)
+                                    .map(|s| s.unwrap_or(0) as f32 / scale),
+                            )
+                        }
+                    }
+
+                    if interleaved.len() == before {
+                        if !*looping {
+                            return false;
+                        }
+                        if reader.seek(0).is_err() {
+                            return false;
+                        }
+                        *frames_read = 0;
+                        loop_start = interleaved.len() / channels;
+                    }
+                }
+
+                for frame in interleaved.chunks_exact(channels) {
+                    out.push(frame.iter().sum::<f32>() / channels as f32);
+                }
+                *frames_read += (count - loop_start) as u64;
+                true
+            }
+        }
+    }
+
+    /// Seconds into a WAV file, used to keep sequences in time with the audio
+    pub fn position(&self) -> Option<f64> {
+        match self {
+            AudioSource::Raw { .. } => None,
+            AudioSource::Wav {
+                reader,
+                frames_read,
+                ..
+            } => Some(*frames_read as f64 / reader.spec().sample_rate as f64),
+        }
+    }
+}
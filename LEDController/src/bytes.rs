@@ -0,0 +1,15 @@
+/// Reads a little endian u16 at `at`, or None if the data ends first
+pub fn read_u16(data: &[u8], at: usize) -> Option<usize> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as usize)
+}
+
+/// Reads a little endian 3 byte number at `at`, or None if the data ends first
+pub fn read_u24(data: &[u8], at: usize) -> Option<usize> {
+    let bytes = data.get(at..at + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]) as usize)
+}
+
+/// Reads a little endian u32 at `at`, or None if the data ends first
+pub fn read_u32(data: &[u8], at: usize) -> Option<usize> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize)
+}
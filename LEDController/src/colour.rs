@@ -60,40 +60,51 @@ impl Colour {
         Colour { h, s, v }
     }
 
+    /// Blends in HSV, taking the shortest way around the hue wheel
     pub fn lerp(c1: Colour, c2: Colour, t: f32) -> Colour {
         let t1 = 1. - t;
         let t2 = t;
-        let h = (c1.h * t1 + c2.h * t2).clamp(0., 360.);
+
+        // Black has no real hue or saturation and greys have no real hue, so borrow
+        // the other colour's rather than sweeping through unrelated colours on the way
+        let c1 = if c1.v <= 0. {
+            Colour::new(c2.h, c2.s, 0.)
+        } else {
+            c1
+        };
+        let c2 = if c2.v <= 0. {
+            Colour::new(c1.h, c1.s, 0.)
+        } else {
+            c2
+        };
+        let (h1, h2) = match (c1.is_grey(), c2.is_grey()) {
+            (true, false) => (c2.h, c2.h),
+            (false, true) => (c1.h, c1.h),
+            _ => (c1.h, c2.h),
+        };
+
+        let h = lerp_hue(h1, h2, t);
         let s = (c1.s * t1 + c2.s * t2).clamp(0., 1.);
         let v = (c1.v * t1 + c2.v * t2).clamp(0., 1.);
         Colour { h, s, v }
     }
 
-    pub fn to_rgb(hsv: &Colour) -> (u8, u8, u8) {
-        let c = hsv.v * hsv.s;
-        let h = (hsv.h % 360.) / 60.;
-        let x = c * (1. - (h % 2. - 1.).abs());
-
-        let (r1, g1, b1) = if h >= 0. && h < 1. {
-            (c, x, 0.)
-        } else if h >= 1. && h < 2. {
-            (x, c, 0.)
-        } else if h >= 2. && h < 3. {
-            (0., c, x)
-        } else if h >= 3. && h < 4. {
-            (0., x, c)
-        } else if h >= 4. && h < 5. {
-            (x, 0., c)
-        } else {
-            (c, 0., x)
-        };
+    /// Perceptual blend through OKLab, which keeps the brightness even across the
+    /// transition and avoids the muddy or overly bright middles of HSV and RGB
+    pub fn blend(c1: Colour, c2: Colour, t: f32) -> Colour {
+        Colour::from(OkLab::lerp(OkLab::from(c1), OkLab::from(c2), t))
+    }
 
-        let m = hsv.v - c;
-        let r = 255. * (r1 + m);
-        let g = 255. * (g1 + m);
-        let b = 255. * (b1 + m);
+    fn is_grey(&self) -> bool {
+        self.s <= 0.
+    }
+
+    pub fn to_rgb(hsv: &Colour) -> (u8, u8, u8) {
+        Rgb::from(*hsv).to_bytes()
+    }
 
-        (r.round() as u8, g.round() as u8, b.round() as u8)
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Colour {
+        Colour::from(Rgb::from_bytes(r, g, b))
     }
 
     pub fn to_string(&self) -> String {
@@ -106,3 +117,284 @@ impl std::fmt::Display for Colour {
         write!(f, "{} {} {}", self.h, self.s, self.v)
     }
 }
+
+/// Moves from one hue to another by the shortest way around the wheel, in degrees
+pub fn lerp_hue(from: f32, to: f32, t: f32) -> f32 {
+    let mut difference = (to - from).rem_euclid(360.);
+    if difference > 180. {
+        difference -= 360.;
+    }
+    (from + difference * t).rem_euclid(360.)
+}
+
+/// Hue in degrees from RGB channels, given the largest channel and the chroma
+fn hue_from_rgb(r: f32, g: f32, b: f32, max: f32, chroma: f32) -> f32 {
+    if chroma == 0. {
+        0.
+    } else if max == r {
+        60. * ((g - b) / chroma).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / chroma + 2.)
+    } else {
+        60. * ((r - g) / chroma + 4.)
+    }
+}
+
+/// RGB channels with the given hue and chroma, before the lightness offset is added
+fn rgb_from_hue(h: f32, chroma: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.) / 60.;
+    let x = chroma * (1. - (h % 2. - 1.).abs());
+
+    match h as u32 {
+        0 => (chroma, x, 0.),
+        1 => (x, chroma, 0.),
+        2 => (0., chroma, x),
+        3 => (0., x, chroma),
+        4 => (x, 0., chroma),
+        _ => (chroma, 0., x),
+    }
+}
+
+/// sRGB with each channel from 0 - 1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Rgb {
+    pub fn new(r: f32, g: f32, b: f32) -> Rgb {
+        Rgb { r, g, b }
+    }
+
+    pub fn from_bytes(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb::new(r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+    }
+
+    pub fn to_bytes(self) -> (u8, u8, u8) {
+        let byte = |c: f32| (255. * c.clamp(0., 1.)).round() as u8;
+        (byte(self.r), byte(self.g), byte(self.b))
+    }
+
+    pub fn lerp(c1: Rgb, c2: Rgb, t: f32) -> Rgb {
+        Rgb::new(
+            c1.r + (c2.r - c1.r) * t,
+            c1.g + (c2.g - c1.g) * t,
+            c1.b + (c2.b - c1.b) * t,
+        )
+    }
+}
+
+impl From<Colour> for Rgb {
+    fn from(hsv: Colour) -> Rgb {
+        let chroma = hsv.v * hsv.s;
+        let (r, g, b) = rgb_from_hue(hsv.h, chroma);
+        let m = hsv.v - chroma;
+        Rgb::new(r + m, g + m, b + m)
+    }
+}
+
+impl From<Rgb> for Colour {
+    fn from(rgb: Rgb) -> Colour {
+        let max = rgb.r.max(rgb.g).max(rgb.b);
+        let min = rgb.r.min(rgb.g).min(rgb.b);
+        let chroma = max - min;
+
+        Colour {
+            h: hue_from_rgb(rgb.r, rgb.g, rgb.b, max, chroma),
+            s: if max <= 0. { 0. } else { chroma / max },
+            v: max.clamp(0., 1.),
+        }
+    }
+}
+
+/// Hue in degrees, saturation and lightness, where lightness 1 is always white
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+impl Hsl {
+    pub fn new(h: f32, s: f32, l: f32) -> Hsl {
+        Hsl { h, s, l }
+    }
+}
+
+impl From<Rgb> for Hsl {
+    fn from(rgb: Rgb) -> Hsl {
+        let max = rgb.r.max(rgb.g).max(rgb.b);
+        let min = rgb.r.min(rgb.g).min(rgb.b);
+        let chroma = max - min;
+        let l = (max + min) / 2.;
+
+        let s = if chroma == 0. {
+            0.
+        } else {
+            chroma / (1. - (2. * l - 1.).abs())
+        };
+
+        Hsl::new(hue_from_rgb(rgb.r, rgb.g, rgb.b, max, chroma), s, l)
+    }
+}
+
+impl From<Hsl> for Rgb {
+    fn from(hsl: Hsl) -> Rgb {
+        let chroma = (1. - (2. * hsl.l - 1.).abs()) * hsl.s;
+        let (r, g, b) = rgb_from_hue(hsl.h, chroma);
+        let m = hsl.l - chroma / 2.;
+        Rgb::new(r + m, g + m, b + m)
+    }
+}
+
+impl From<Colour> for Hsl {
+    fn from(hsv: Colour) -> Hsl {
+        Hsl::from(Rgb::from(hsv))
+    }
+}
+
+impl From<Hsl> for Colour {
+    fn from(hsl: Hsl) -> Colour {
+        Colour::from(Rgb::from(hsl))
+    }
+}
+
+/// Björn Ottosson's OKLab, where equal distances look like equal differences in colour.
+/// `l` is the lightness from 0 - 1, `a` runs green to red and `b` blue to yellow
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OkLab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl OkLab {
+    pub fn new(l: f32, a: f32, b: f32) -> OkLab {
+        OkLab { l, a, b }
+    }
+
+    pub fn lerp(c1: OkLab, c2: OkLab, t: f32) -> OkLab {
+        OkLab::new(
+            c1.l + (c2.l - c1.l) * t,
+            c1.a + (c2.a - c1.a) * t,
+            c1.b + (c2.b - c1.b) * t,
+        )
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+impl From<Rgb> for OkLab {
+    fn from(rgb: Rgb) -> OkLab {
+        let r = srgb_to_linear(rgb.r);
+        let g = srgb_to_linear(rgb.g);
+        let b = srgb_to_linear(rgb.b);
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        OkLab::new(
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        )
+    }
+}
+
+impl From<OkLab> for Rgb {
+    /// Colours outside the sRGB gamut are clipped to it
+    fn from(lab: OkLab) -> Rgb {
+        let l = (lab.l + 0.396_337_78 * lab.a + 0.215_803_76 * lab.b).powi(3);
+        let m = (lab.l - 0.105_561_346 * lab.a - 0.063_854_17 * lab.b).powi(3);
+        let s = (lab.l - 0.089_484_18 * lab.a - 1.291_485_5 * lab.b).powi(3);
+
+        let r = 4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s;
+        let g = -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s;
+        let b = -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s;
+
+        let encode = |c: f32| linear_to_srgb(c.clamp(0., 1.));
+        Rgb::new(encode(r), encode(g), encode(b))
+    }
+}
+
+impl From<Colour> for OkLab {
+    fn from(hsv: Colour) -> OkLab {
+        OkLab::from(Rgb::from(hsv))
+    }
+}
+
+impl From<OkLab> for Colour {
+    fn from(lab: OkLab) -> Colour {
+        Colour::from(Rgb::from(lab))
+    }
+}
+
+/// OKLab in polar form: lightness, chroma and hue in degrees
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OkLch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+impl OkLch {
+    pub fn new(l: f32, c: f32, h: f32) -> OkLch {
+        OkLch { l, c, h }
+    }
+
+    /// Blends taking the shortest way around the hue wheel, keeping the chroma up
+    /// where an OKLab blend would pass through grey
+    pub fn lerp(c1: OkLch, c2: OkLch, t: f32) -> OkLch {
+        OkLch::new(
+            c1.l + (c2.l - c1.l) * t,
+            c1.c + (c2.c - c1.c) * t,
+            lerp_hue(c1.h, c2.h, t),
+        )
+    }
+}
+
+impl From<OkLab> for OkLch {
+    fn from(lab: OkLab) -> OkLch {
+        OkLch::new(
+            lab.l,
+            f32::hypot(lab.a, lab.b),
+            f32::atan2(lab.b, lab.a).to_degrees().rem_euclid(360.),
+        )
+    }
+}
+
+impl From<OkLch> for OkLab {
+    fn from(lch: OkLch) -> OkLab {
+        let (sin, cos) = lch.h.to_radians().sin_cos();
+        OkLab::new(lch.l, lch.c * cos, lch.c * sin)
+    }
+}
+
+impl From<Colour> for OkLch {
+    fn from(hsv: Colour) -> OkLch {
+        OkLch::from(OkLab::from(hsv))
+    }
+}
+
+impl From<OkLch> for Colour {
+    fn from(lch: OkLch) -> Colour {
+        Colour::from(OkLab::from(lch))
+    }
+}
@@ -0,0 +1,14 @@
+use std::fs;
+
+use ini::Ini;
+
+use crate::effect::constants::CONFIG_NAME;
+
+/// Writes the settings to a temporary file then renames it over the config, so
+/// being killed part way through saving can never leave a truncated file behind
+pub fn save(config: &Ini) {
+    let temp_name = format!("{}.tmp", CONFIG_NAME);
+
+    config.write_to_file(&temp_name).unwrap();
+    fs::rename(&temp_name, CONFIG_NAME).unwrap();
+}
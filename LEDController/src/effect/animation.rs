@@ -0,0 +1,333 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use std::sync::Arc;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::file_playback::Loader;
+use crate::effect::frame_sequence::FrameSequence;
+use crate::effect::projection::Projection;
+use crate::pixel::Pixel;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum LoopMode {
+    Loop,
+    PingPong,
+    Once,
+}
+
+impl LoopMode {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            LoopMode::Loop => "Loop",
+            LoopMode::PingPong => "Ping Pong",
+            LoopMode::Once => "Once",
+        }
+    }
+
+    pub fn to_id(self) -> u32 {
+        match self {
+            LoopMode::Loop => 0,
+            LoopMode::PingPong => 1,
+            LoopMode::Once => 2,
+        }
+    }
+
+    pub fn from_id(id: u32) -> LoopMode {
+        match id {
+            1 => LoopMode::PingPong,
+            2 => LoopMode::Once,
+            _ => LoopMode::Loop,
+        }
+    }
+
+    pub fn next(self) -> LoopMode {
+        LoopMode::from_id((self.to_id() + 1) % 3)
+    }
+
+    /// Where `time` seconds of playback falls within something `duration` seconds long
+    pub fn position(self, time: f32, duration: f32) -> f32 {
+        if duration <= 0. {
+            return 0.;
+        }
+
+        match self {
+            LoopMode::Loop => time.rem_euclid(duration),
+            LoopMode::PingPong => {
+                let time = time.rem_euclid(2. * duration);
+                if time < duration {
+                    time
+                } else {
+                    2. * duration - time
+                }
+            }
+            LoopMode::Once => time.clamp(0., duration),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AnimationEffect {
+    sequence: Option<Arc<FrameSequence>>,
+    error: Option<String>,
+    loading: Option<Loader<Arc<FrameSequence>>>,
+    /// Set when the path no longer matches the loaded sequence
+    stale: bool,
+    /// Decodes the file again on the next load rather than using the cache
+    evict: bool,
+    path: String,
+    time: f32,
+    projection: Projection,
+    loop_mode: LoopMode,
+    frame_rate: f32,
+    playback_speed: f32,
+}
+
+impl AnimationEffect {
+    pub fn default() -> AnimationEffect {
+        AnimationEffect {
+            sequence: None,
+            error: None,
+            loading: None,
+            stale: true,
+            evict: false,
+            path: String::from("animation.gif"),
+            time: 0.,
+            projection: Projection::Cylindrical,
+            loop_mode: LoopMode::Loop,
+            frame_rate: 0.,
+            playback_speed: 1.,
+        }
+    }
+
+    /// Starts decoding the sequence in the background, unless it is cached
+    fn load_sequence(&mut self) {
+        let path = self.path.clone();
+        let evict = self.evict;
+        self.loading = Some(Loader::start(move || {
+            if evict {
+                FrameSequence::evict(&path);
+            }
+            FrameSequence::load_cached(&path)
+        }));
+        self.stale = false;
+        self.evict = false;
+    }
+
+    /// Time into a single play through of the sequence, after applying the loop mode
+    fn playback_time(&self, duration: f32) -> f32 {
+        self.loop_mode.position(self.time, duration)
+    }
+}
+
+impl EffectTrait for AnimationEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Animation"))
+            .set("path", self.path.clone())
+            .set("projection", format!("{}", self.projection.to_id()))
+            .set("loop_mode", format!("{}", self.loop_mode.to_id()))
+            .set("frame_rate", format!("{:2.0}", self.frame_rate))
+            .set("playback_speed", format!("{:1.2}", self.playback_speed));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Animation")) {
+                if let Some(path) = section.get("path") {
+                    self.path = path.to_string();
+                }
+
+                if let Some(projection) = section.get("projection") {
+                    self.projection = Projection::from_id(projection.parse().unwrap());
+                }
+
+                if let Some(loop_mode) = section.get("loop_mode") {
+                    self.loop_mode = LoopMode::from_id(loop_mode.parse().unwrap());
+                }
+
+                if let Some(frame_rate) = section.get("frame_rate") {
+                    self.frame_rate = frame_rate.parse().unwrap();
+                }
+
+                if let Some(playback_speed) = section.get("playback_speed") {
+                    self.playback_speed = playback_speed.parse().unwrap();
+                }
+            }
+        }
+
+        self.stale = true;
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        if let Some(result) = self.loading.as_ref().and_then(Loader::finished) {
+            match result {
+                Ok(sequence) => {
+                    self.sequence = Some(sequence);
+                    self.error = None;
+                }
+                Err(err) => {
+                    self.sequence = None;
+                    self.error = Some(err);
+                }
+            }
+            self.loading = None;
+            self.time = 0.;
+        }
+
+        // One load at a time, anything changed meanwhile is loaded once it's done
+        if self.loading.is_none() && self.stale {
+            self.load_sequence();
+        }
+
+        self.time += delta * self.playback_speed;
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        let Some(sequence) = &self.sequence else {
+            for pixel in pixels.iter_mut() {
+                pixel.colour = BLACK;
+            }
+            return;
+        };
+
+        let time = self.playback_time(sequence.duration(self.frame_rate));
+        let texture = sequence.frame_at(time, self.frame_rate);
+
+        for pixel in pixels.iter_mut() {
+            let (u, v) = self.projection.uv(pixel.position, 0.);
+            pixel.colour = texture.sample(u, v);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('c') => self.projection = self.projection.next(),
+            KeyCode::Char('b') => self.loop_mode = self.loop_mode.next(),
+            KeyCode::Char('l') => {
+                self.stale = true;
+                self.evict = true;
+            }
+            KeyCode::Char('s') => self.time = 0.,
+
+            KeyCode::Char('n') => self.frame_rate = (self.frame_rate - 1.).clamp(0., 120.),
+            KeyCode::Char('m') => self.frame_rate = (self.frame_rate + 1.).clamp(0., 120.),
+
+            KeyCode::Char('j') => self.playback_speed = (self.playback_speed - 0.1).clamp(-4., 4.),
+            KeyCode::Char('k') => self.playback_speed = (self.playback_speed + 0.1).clamp(-4., 4.),
+            _ => {}
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some() || self.stale
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let status = match (&self.sequence, &self.error) {
+            _ if self.loading.is_some() => Span::styled(
+                format!("Loading {}", self.path),
+                Style::default().fg(Color::Yellow),
+            ),
+            (Some(sequence), _) => Span::styled(
+                format!("{} ({} frames)", self.path, sequence.len()),
+                Style::default().fg(Color::Green),
+            ),
+            (None, Some(err)) => Span::styled(
+                format!("{}: {}", self.path, err),
+                Style::default().fg(Color::Red),
+            ),
+            (None, None) => Span::styled(self.path.clone(), Style::default().fg(Color::Red)),
+        };
+
+        let frame_rate = if self.frame_rate > 0. {
+            format!("{:2.0} FPS", self.frame_rate)
+        } else {
+            String::from("From File")
+        };
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("l ", Style::default().fg(Color::Green)),
+                status,
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Projection: {}", self.projection.to_string()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Loop Mode: {}", self.loop_mode.to_string()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Frame Rate: {}", frame_rate),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("j ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Playback Speed: {:1.1}x", self.playback_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" k", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("s ", Style::default().fg(Color::Green)),
+                Span::styled("Restart", Style::default().fg(Color::White)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -0,0 +1,434 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+use rand;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+
+const MAX_BALLS: usize = 20;
+const MIN_LAUNCH_SPEED: f32 = 150.;
+const MAX_LAUNCH_SPEED: f32 = 400.;
+const REST_SPEED: f32 = 20.;
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum BallColourMode {
+    Random,
+    Spread,
+    Speed,
+}
+
+impl BallColourMode {
+    fn to_string(self) -> &'static str {
+        match self {
+            BallColourMode::Random => "Random",
+            BallColourMode::Spread => "Spread",
+            BallColourMode::Speed => "Speed",
+        }
+    }
+
+    fn to_id(self) -> u32 {
+        match self {
+            BallColourMode::Random => 0,
+            BallColourMode::Spread => 1,
+            BallColourMode::Speed => 2,
+        }
+    }
+
+    fn from_id(id: u32) -> BallColourMode {
+        match id {
+            1 => BallColourMode::Spread,
+            2 => BallColourMode::Speed,
+            _ => BallColourMode::Random,
+        }
+    }
+
+    fn next(self) -> BallColourMode {
+        BallColourMode::from_id((self.to_id() + 1) % 3)
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Ball {
+    pos: Vec3,
+    vel: Vec3,
+    /// Where the ball's colour is taken from the palette in the random colour mode
+    palette_position: f32,
+}
+
+impl Ball {
+    fn random() -> Ball {
+        let pos = Vec3::new(
+            MIN_X + rand::random::<f32>() * WIDTH,
+            MIN_Y + HEIGHT / 2. + rand::random::<f32>() * HEIGHT / 2.,
+            MIN_Z + rand::random::<f32>() * DEPTH,
+        );
+
+        let mut ball = Ball {
+            pos,
+            vel: Vec3::new(0., 0., 0.),
+            palette_position: rand::random::<f32>(),
+        };
+        ball.launch();
+        ball
+    }
+
+    fn launch(&mut self) {
+        let theta = rand::random::<f32>() * 2. * std::f32::consts::PI;
+        let direction = Vec3::new(
+            f32::cos(theta),
+            0.5 + rand::random::<f32>(),
+            f32::sin(theta),
+        );
+        let speed =
+            MIN_LAUNCH_SPEED + rand::random::<f32>() * (MAX_LAUNCH_SPEED - MIN_LAUNCH_SPEED);
+
+        self.vel = Vec3::mul_scalar(Vec3::norm(direction), speed);
+    }
+
+    fn is_resting(&self) -> bool {
+        self.pos.y <= MIN_Y + 1. && Vec3::mag(self.vel) < REST_SPEED
+    }
+}
+
+#[derive(Clone)]
+pub struct BouncingBallsEffect {
+    balls: Vec<Ball>,
+    ball_count: usize,
+    radius: f32,
+    gravity: f32,
+    restitution: f32,
+    decay: f32,
+    colour_mode: BallColourMode,
+    collide: bool,
+    palette: Palette,
+}
+
+impl BouncingBallsEffect {
+    pub fn default() -> BouncingBallsEffect {
+        BouncingBallsEffect {
+            balls: Vec::new(),
+            ball_count: 4,
+            radius: 40.,
+            gravity: 300.,
+            restitution: 0.85,
+            decay: 0.8,
+            colour_mode: BallColourMode::Random,
+            collide: true,
+            palette: Palette::rainbow(),
+        }
+    }
+
+    fn ball_colour(&self, index: usize, ball: &Ball) -> Colour {
+        match self.colour_mode {
+            BallColourMode::Random => self.palette.sample(ball.palette_position),
+            BallColourMode::Spread => self.palette.sample(index as f32 / self.balls.len() as f32),
+            BallColourMode::Speed => {
+                let speed = (Vec3::mag(ball.vel) / MAX_LAUNCH_SPEED).clamp(0., 1.);
+                self.palette.sample(240. * (1. - speed) / 360.)
+            }
+        }
+    }
+
+    fn intensity(&self, ball: &Ball, pixel: &Pixel) -> f32 {
+        let distance = Vec3::distance(pixel.position, ball.pos);
+        if distance >= self.radius {
+            return 0.;
+        }
+
+        let t = 1. - distance / self.radius;
+        t * t * (3. - 2. * t)
+    }
+
+    fn bounce_off_bounds(&self, ball: &mut Ball) {
+        let restitution = self.restitution;
+        let bounce = |pos: &mut f32, vel: &mut f32, min: f32, max: f32| {
+            if *pos < min {
+                *pos = min;
+                *vel = vel.abs() * restitution;
+            } else if *pos > max {
+                *pos = max;
+                *vel = -vel.abs() * restitution;
+            }
+        };
+
+        bounce(&mut ball.pos.x, &mut ball.vel.x, MIN_X, MAX_X);
+        bounce(&mut ball.pos.y, &mut ball.vel.y, MIN_Y, MAX_Y);
+        bounce(&mut ball.pos.z, &mut ball.vel.z, MIN_Z, MAX_Z);
+    }
+
+    fn collide_balls(&mut self) {
+        let min_distance = self.radius * 2.;
+
+        for i in 0..self.balls.len() {
+            for j in (i + 1)..self.balls.len() {
+                let offset = Vec3::sub(self.balls[j].pos, self.balls[i].pos);
+                let distance = Vec3::mag(offset);
+                if distance >= min_distance || distance == 0. {
+                    continue;
+                }
+
+                let normal = Vec3::mul_scalar(offset, 1. / distance);
+                let approach = Vec3::dot(Vec3::sub(self.balls[i].vel, self.balls[j].vel), normal);
+
+                // Push the balls apart so they don't stick together
+                let correction = Vec3::mul_scalar(normal, (min_distance - distance) / 2.);
+                self.balls[i].pos = Vec3::sub(self.balls[i].pos, correction);
+                self.balls[j].pos = Vec3::add(self.balls[j].pos, correction);
+
+                if approach <= 0. {
+                    continue;
+                }
+
+                // Equal masses, so the balls exchange the velocity along the normal
+                let impulse = Vec3::mul_scalar(normal, approach * (1. + self.restitution) / 2.);
+                self.balls[i].vel = Vec3::sub(self.balls[i].vel, impulse);
+                self.balls[j].vel = Vec3::add(self.balls[j].vel, impulse);
+            }
+        }
+    }
+}
+
+impl EffectTrait for BouncingBallsEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.BouncingBalls"))
+            .set("palette", self.palette.name())
+            .set("ball_count", format!("{}", self.ball_count))
+            .set("radius", format!("{:3.0}", self.radius))
+            .set("gravity", format!("{:3.0}", self.gravity))
+            .set("restitution", format!("{:1.2}", self.restitution))
+            .set("decay", format!("{:1.2}", self.decay))
+            .set("colour_mode", format!("{}", self.colour_mode.to_id()))
+            .set("collide", format!("{}", self.collide));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.BouncingBalls")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(ball_count) = section.get("ball_count") {
+                    self.ball_count = ball_count.parse::<usize>().unwrap().clamp(1, MAX_BALLS);
+                }
+
+                if let Some(radius) = section.get("radius") {
+                    self.radius = radius.parse().unwrap();
+                }
+
+                if let Some(gravity) = section.get("gravity") {
+                    self.gravity = gravity.parse().unwrap();
+                }
+
+                if let Some(restitution) = section.get("restitution") {
+                    self.restitution = restitution.parse().unwrap();
+                }
+
+                if let Some(decay) = section.get("decay") {
+                    self.decay = decay.parse().unwrap();
+                }
+
+                if let Some(colour_mode) = section.get("colour_mode") {
+                    self.colour_mode = BallColourMode::from_id(colour_mode.parse().unwrap());
+                }
+
+                if let Some(collide) = section.get("collide") {
+                    self.collide = collide.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        self.balls.truncate(self.ball_count);
+        while self.balls.len() < self.ball_count {
+            self.balls.push(Ball::random());
+        }
+
+        let gravity = Vec3::new(0., -self.gravity * delta, 0.);
+        let mut balls = std::mem::take(&mut self.balls);
+        for ball in balls.iter_mut() {
+            ball.vel = Vec3::add(ball.vel, gravity);
+            ball.pos = Vec3::add(ball.pos, Vec3::mul_scalar(ball.vel, delta));
+            self.bounce_off_bounds(ball);
+        }
+        self.balls = balls;
+
+        if self.collide {
+            self.collide_balls();
+        }
+
+        if self.balls.iter().all(|ball| ball.is_resting()) {
+            for ball in self.balls.iter_mut() {
+                ball.launch();
+                ball.palette_position = rand::random::<f32>();
+            }
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        for pixel in pixels.iter_mut() {
+            let mut brightest: Option<(f32, Colour)> = None;
+            for (i, ball) in self.balls.iter().enumerate() {
+                let intensity = self.intensity(ball, pixel);
+                if intensity > brightest.map_or(0., |(v, _)| v) {
+                    brightest = Some((intensity, self.ball_colour(i, ball)));
+                }
+            }
+
+            let mut new_value = pixel.colour.v * self.decay;
+            if new_value < 0.05 {
+                new_value = 0.;
+            }
+
+            match brightest {
+                Some((intensity, colour)) if intensity >= new_value => {
+                    pixel.colour = Colour::new(colour.h, colour.s, colour.v * intensity);
+                }
+                _ => {
+                    pixel.colour = Colour::new(pixel.colour.h, pixel.colour.s, new_value);
+                }
+            }
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Up => self.ball_count = (self.ball_count + 1).clamp(1, MAX_BALLS),
+            KeyCode::Down => {
+                self.ball_count = (self.ball_count.saturating_sub(1)).clamp(1, MAX_BALLS)
+            }
+
+            KeyCode::Char('K') => self.radius = (self.radius + 10.).clamp(5., 200.),
+            KeyCode::Char('k') => self.radius = (self.radius + 5.).clamp(5., 200.),
+            KeyCode::Char('J') => self.radius = (self.radius - 10.).clamp(5., 200.),
+            KeyCode::Char('j') => self.radius = (self.radius - 5.).clamp(5., 200.),
+
+            KeyCode::Char('a') => self.gravity = (self.gravity - 25.).clamp(0., 2000.),
+            KeyCode::Char('s') => self.gravity = (self.gravity + 25.).clamp(0., 2000.),
+
+            KeyCode::Char('z') => self.restitution = (self.restitution - 0.05).clamp(0., 1.),
+            KeyCode::Char('x') => self.restitution = (self.restitution + 0.05).clamp(0., 1.),
+
+            KeyCode::Char('n') => self.decay = (self.decay - 0.01).clamp(0., 1.),
+            KeyCode::Char('m') => self.decay = (self.decay + 0.01).clamp(0., 1.),
+
+            KeyCode::Char('c') => self.colour_mode = self.colour_mode.next(),
+            KeyCode::Char('b') => self.collide = !self.collide,
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Balls: {:2}", self.ball_count),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Radius: {:3.0}", self.radius),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Gravity: {:4.0}", self.gravity),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Restitution: {:1.2}", self.restitution),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Decay: {:1.2}", self.decay),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Colour Mode: {}", self.colour_mode.to_string()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Collisions: {}", if self.collide { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -1,22 +1,50 @@
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{layout::Rect, Frame};
 
+use crate::effect::animation::AnimationEffect;
+use crate::effect::bouncing_balls::BouncingBallsEffect;
 use crate::effect::effect_trait::EffectTrait;
 use crate::effect::expanding_circle::ExpandingCircleEffect;
+use crate::effect::fireworks::FireworksEffect;
+use crate::effect::game_of_life::GameOfLifeEffect;
+use crate::effect::image_projection::ImageProjectionEffect;
+use crate::effect::matrix_rain::MatrixRainEffect;
+use crate::effect::metaballs::MetaballsEffect;
+use crate::effect::playback::PlaybackEffect;
 use crate::effect::rainbow_plane::RainbowPlaneEffect;
 use crate::effect::random_moving_plane::RandomMovingPlaneEffect;
+use crate::effect::scrolling_text::ScrollingTextEffect;
+use crate::effect::sequence::SequenceEffect;
 use crate::effect::solid_colour::SolidColourEffect;
+use crate::effect::spectrum::SpectrumEffect;
+use crate::effect::strand_chase::StrandChaseEffect;
 use crate::pixel::Pixel;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub enum Effect {
     SolidColour(SolidColourEffect),
     RainbowPlane(RainbowPlaneEffect),
     RandomMovingPlane(RandomMovingPlaneEffect),
     ExpandingCircle(ExpandingCircleEffect),
+    BouncingBalls(BouncingBallsEffect),
+    Fireworks(FireworksEffect),
+    GameOfLife(GameOfLifeEffect),
+    MatrixRain(MatrixRainEffect),
+    Comet(StrandChaseEffect),
+    TheatreChase(StrandChaseEffect),
+    LarsonScanner(StrandChaseEffect),
+    ColourWipe(StrandChaseEffect),
+    Spectrum(SpectrumEffect),
+    BeatCircle(ExpandingCircleEffect),
+    ImageProjection(ImageProjectionEffect),
+    Animation(AnimationEffect),
+    ScrollingText(ScrollingTextEffect),
+    Metaballs(MetaballsEffect),
+    Playback(PlaybackEffect),
+    Sequence(SequenceEffect),
 }
 
-const NUM_EFFECTS: i32 = 4;
+const NUM_EFFECTS: i32 = 20;
 
 impl Effect {
     pub fn to_string(&self) -> &str {
@@ -25,6 +53,22 @@ impl Effect {
             Effect::RainbowPlane(_) => "Rainbow Plane",
             Effect::RandomMovingPlane(_) => "Rainbow Moving Plane",
             Effect::ExpandingCircle(_) => "Expanding Circle",
+            Effect::BouncingBalls(_) => "Bouncing Balls",
+            Effect::Fireworks(_) => "Fireworks",
+            Effect::GameOfLife(_) => "Game of Life",
+            Effect::MatrixRain(_) => "Matrix Rain",
+            Effect::Comet(_) => "Comet",
+            Effect::TheatreChase(_) => "Theatre Chase",
+            Effect::LarsonScanner(_) => "Larson Scanner",
+            Effect::ColourWipe(_) => "Colour Wipe",
+            Effect::Spectrum(_) => "Spectrum",
+            Effect::BeatCircle(_) => "Beat Circle",
+            Effect::ImageProjection(_) => "Image Projection",
+            Effect::Animation(_) => "Animation",
+            Effect::ScrollingText(_) => "Scrolling Text",
+            Effect::Metaballs(_) => "Metaballs",
+            Effect::Playback(_) => "Playback",
+            Effect::Sequence(_) => "Sequence",
         }
     }
 
@@ -45,18 +89,31 @@ impl Effect {
     }
 
     pub fn handle_input(&mut self, event: KeyEvent) {
+        if self.captures_input() {
+            self.decompose_mut().handle_input(event);
+            return;
+        }
+
         match event.code {
-            KeyCode::Char('r') => *self = Effect::default_effect(Effect::effect_to_id(*self)),
+            KeyCode::Char('r') => *self = Effect::default_effect(Effect::effect_to_id(self)),
             _ => self.decompose_mut().handle_input(event),
         }
     }
 
+    pub fn captures_input(&self) -> bool {
+        self.decompose().captures_input()
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.decompose().is_loading()
+    }
+
     pub fn draw(&self, frame: &mut Frame, layout: Rect) {
         self.decompose().draw(frame, layout);
     }
 
     pub fn change_effect(&mut self, offset: i32) {
-        let current_id = Effect::effect_to_id(*self);
+        let current_id = Effect::effect_to_id(self);
         let new_id_unnormalized = current_id + offset;
         let new_id = if new_id_unnormalized < 0 {
             NUM_EFFECTS - 1
@@ -68,12 +125,28 @@ impl Effect {
         *self = Effect::id_to_effect(new_id);
     }
 
-    pub fn decompose(&self) -> Box<dyn EffectTrait> {
+    pub fn decompose(&self) -> &dyn EffectTrait {
         match self {
-            Effect::SolidColour(e) => Box::new(*e) as Box<dyn EffectTrait>,
-            Effect::RainbowPlane(e) => Box::new(*e) as Box<dyn EffectTrait>,
-            Effect::RandomMovingPlane(e) => Box::new(*e) as Box<dyn EffectTrait>,
-            Effect::ExpandingCircle(e) => Box::new(*e) as Box<dyn EffectTrait>,
+            Effect::SolidColour(e) => e.as_trait(),
+            Effect::RainbowPlane(e) => e.as_trait(),
+            Effect::RandomMovingPlane(e) => e.as_trait(),
+            Effect::ExpandingCircle(e) => e.as_trait(),
+            Effect::BouncingBalls(e) => e.as_trait(),
+            Effect::Fireworks(e) => e.as_trait(),
+            Effect::GameOfLife(e) => e.as_trait(),
+            Effect::MatrixRain(e) => e.as_trait(),
+            Effect::Comet(e) => e.as_trait(),
+            Effect::TheatreChase(e) => e.as_trait(),
+            Effect::LarsonScanner(e) => e.as_trait(),
+            Effect::ColourWipe(e) => e.as_trait(),
+            Effect::Spectrum(e) => e.as_trait(),
+            Effect::BeatCircle(e) => e.as_trait(),
+            Effect::ImageProjection(e) => e.as_trait(),
+            Effect::Animation(e) => e.as_trait(),
+            Effect::ScrollingText(e) => e.as_trait(),
+            Effect::Metaballs(e) => e.as_trait(),
+            Effect::Playback(e) => e.as_trait(),
+            Effect::Sequence(e) => e.as_trait(),
         }
     }
 
@@ -83,15 +156,47 @@ impl Effect {
             Effect::RainbowPlane(e) => e.as_trait_mut(),
             Effect::RandomMovingPlane(e) => e.as_trait_mut(),
             Effect::ExpandingCircle(e) => e.as_trait_mut(),
+            Effect::BouncingBalls(e) => e.as_trait_mut(),
+            Effect::Fireworks(e) => e.as_trait_mut(),
+            Effect::GameOfLife(e) => e.as_trait_mut(),
+            Effect::MatrixRain(e) => e.as_trait_mut(),
+            Effect::Comet(e) => e.as_trait_mut(),
+            Effect::TheatreChase(e) => e.as_trait_mut(),
+            Effect::LarsonScanner(e) => e.as_trait_mut(),
+            Effect::ColourWipe(e) => e.as_trait_mut(),
+            Effect::Spectrum(e) => e.as_trait_mut(),
+            Effect::BeatCircle(e) => e.as_trait_mut(),
+            Effect::ImageProjection(e) => e.as_trait_mut(),
+            Effect::Animation(e) => e.as_trait_mut(),
+            Effect::ScrollingText(e) => e.as_trait_mut(),
+            Effect::Metaballs(e) => e.as_trait_mut(),
+            Effect::Playback(e) => e.as_trait_mut(),
+            Effect::Sequence(e) => e.as_trait_mut(),
         }
     }
 
-    pub fn effect_to_id(effect: Effect) -> i32 {
+    pub fn effect_to_id(effect: &Effect) -> i32 {
         match effect {
             Effect::SolidColour(_) => 0,
             Effect::RainbowPlane(_) => 1,
             Effect::RandomMovingPlane(_) => 2,
             Effect::ExpandingCircle(_) => 3,
+            Effect::BouncingBalls(_) => 4,
+            Effect::Fireworks(_) => 5,
+            Effect::GameOfLife(_) => 6,
+            Effect::MatrixRain(_) => 7,
+            Effect::Comet(_) => 8,
+            Effect::TheatreChase(_) => 9,
+            Effect::LarsonScanner(_) => 10,
+            Effect::ColourWipe(_) => 11,
+            Effect::Spectrum(_) => 12,
+            Effect::BeatCircle(_) => 13,
+            Effect::ImageProjection(_) => 14,
+            Effect::Animation(_) => 15,
+            Effect::ScrollingText(_) => 16,
+            Effect::Metaballs(_) => 17,
+            Effect::Playback(_) => 18,
+            Effect::Sequence(_) => 19,
         }
     }
 
@@ -101,10 +206,34 @@ impl Effect {
             1 => Effect::RainbowPlane(RainbowPlaneEffect::default()),
             2 => Effect::RandomMovingPlane(RandomMovingPlaneEffect::default()),
             3 => Effect::ExpandingCircle(ExpandingCircleEffect::default()),
+            4 => Effect::BouncingBalls(BouncingBallsEffect::default()),
+            5 => Effect::Fireworks(FireworksEffect::default()),
+            6 => Effect::GameOfLife(GameOfLifeEffect::default()),
+            7 => Effect::MatrixRain(MatrixRainEffect::default()),
+            8 => Effect::Comet(StrandChaseEffect::comet()),
+            9 => Effect::TheatreChase(StrandChaseEffect::theatre_chase()),
+            10 => Effect::LarsonScanner(StrandChaseEffect::larson_scanner()),
+            11 => Effect::ColourWipe(StrandChaseEffect::colour_wipe()),
+            12 => Effect::Spectrum(SpectrumEffect::default()),
+            13 => Effect::BeatCircle(ExpandingCircleEffect::beat_pulse()),
+            14 => Effect::ImageProjection(ImageProjectionEffect::default()),
+            15 => Effect::Animation(AnimationEffect::default()),
+            16 => Effect::ScrollingText(ScrollingTextEffect::default()),
+            17 => Effect::Metaballs(MetaballsEffect::default()),
+            18 => Effect::Playback(PlaybackEffect::default()),
+            19 => Effect::Sequence(SequenceEffect::default()),
             _ => panic!("Undefined ID"),
         }
     }
 
+    /// Looks an effect up by its displayed name, ignoring case and spaces
+    pub fn name_to_id(name: &str) -> Option<i32> {
+        let simplify = |n: &str| n.replace(' ', "").to_lowercase();
+        let name = simplify(name);
+
+        (0..NUM_EFFECTS).find(|id| simplify(Effect::default_effect(*id).to_string()) == name)
+    }
+
     pub fn id_to_effect(effect_id: i32) -> Effect {
         let mut effect = Effect::default_effect(effect_id);
         effect.read_settings();
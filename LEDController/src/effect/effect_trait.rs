@@ -4,6 +4,7 @@ use ratatui::{layout::Rect, Frame};
 use crate::pixel::Pixel;
 
 pub trait EffectTrait {
+    fn as_trait(&self) -> &dyn EffectTrait;
     fn as_trait_mut(&mut self) -> &mut dyn EffectTrait;
 
     fn save_settings(&self);
@@ -13,5 +14,13 @@ pub trait EffectTrait {
     fn render(&self, pixels: &mut Vec<Pixel>);
 
     fn handle_input(&mut self, event: KeyEvent);
+    /// While true every key press goes to the effect, e.g. when typing text
+    fn captures_input(&self) -> bool {
+        false
+    }
+    /// True while a file is still being loaded in the background
+    fn is_loading(&self) -> bool {
+        false
+    }
     fn draw(&self, frame: &mut Frame, layout: Rect);
 }
@@ -10,17 +10,25 @@ use ratatui::{
 use ini::Ini;
 use rand;
 
+use crate::audio;
 use crate::colour::*;
+use crate::config;
 use crate::effect::constants::*;
 use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
 use crate::pixel::Pixel;
 use crate::vec3::Vec3;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ExpandingCircleEffect {
     radius: f32,
     expansion_speed: f32,
     colour: Colour,
+    on_beat: bool,
+    last_beat: u64,
+    section: &'static str,
+    palette: Palette,
+    palette_position: f32,
 }
 
 impl ExpandingCircleEffect {
@@ -29,22 +37,36 @@ impl ExpandingCircleEffect {
             radius: 0.,
             expansion_speed: 50.,
             colour: BLACK,
+            on_beat: false,
+            last_beat: 0,
+            section: "Effect.ExpandingCircle",
+            palette: Palette::rainbow(),
+            palette_position: 0.,
         };
 
         eff.random_colour();
         eff
     }
 
+    /// Restarts the circle from the centre on every beat of the audio input
+    pub fn beat_pulse() -> ExpandingCircleEffect {
+        let mut eff = ExpandingCircleEffect::default();
+        eff.expansion_speed = 400.;
+        eff.on_beat = true;
+        eff.last_beat = audio::features().beat_count;
+        eff.section = "Effect.BeatCircle";
+        eff
+    }
+
     pub fn random_colour(&mut self) {
-        let hue = (self.colour.h + (60. + f32::round(rand::random::<f32>() * 240.))) % 360.;
-        self.colour = Colour::new(hue, 1., 1.);
+        let step = (60. + f32::round(rand::random::<f32>() * 240.)) / 360.;
+        self.palette_position = (self.palette_position + step) % 1.;
+        self.colour = self.palette.sample(self.palette_position);
     }
 
     fn should_be_coloured(&self, pixel: &Pixel) -> Result<f32, ()> {
         let center = Vec3::new(0., HEIGHT / 2., 0.);
-        let new_position = Vec3::sub(pixel.position, center);
-
-        let distance = Vec3::mag(new_position);
+        let distance = Vec3::distance(pixel.position, center);
 
         if distance < self.radius {
             Ok(distance)
@@ -55,6 +77,10 @@ impl ExpandingCircleEffect {
 }
 
 impl EffectTrait for ExpandingCircleEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
     fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
         self
     }
@@ -66,15 +92,20 @@ impl EffectTrait for ExpandingCircleEffect {
         }
 
         config
-            .with_section(Some("Effect.ExpandingCircle"))
+            .with_section(Some(self.section))
+            .set("palette", self.palette.name())
             .set("expansion_speed", format!("{:3.0}", self.expansion_speed));
 
-        config.write_to_file(CONFIG_NAME).unwrap();
+        config::save(&config);
     }
 
     fn read_settings(&mut self) {
         if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
-            if let Some(section) = config.section(Some("Effect.ExpandingCircle")) {
+            if let Some(section) = config.section(Some(self.section)) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
                 if let Some(expansion_speed) = section.get("expansion_speed") {
                     self.expansion_speed = expansion_speed.parse().unwrap();
                 }
@@ -85,6 +116,16 @@ impl EffectTrait for ExpandingCircleEffect {
     fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
         self.radius += self.expansion_speed * delta;
 
+        if self.on_beat {
+            let beat_count = audio::features().beat_count;
+            if beat_count != self.last_beat {
+                self.last_beat = beat_count;
+                self.random_colour();
+                self.radius = 0.;
+            }
+            return;
+        }
+
         let mut all_coloured = true;
 
         for pixel in pixels.iter() {
@@ -124,6 +165,7 @@ impl EffectTrait for ExpandingCircleEffect {
             }
             // KeyCode::Up => self.multiplier = (self.multiplier + 1).clamp(1, 10),
             // KeyCode::Down => self.multiplier = (self.multiplier - 1).clamp(1, 10),
+            KeyCode::Char('p') => self.palette = self.palette.next(),
             _ => {}
         }
     }
@@ -143,6 +185,13 @@ impl EffectTrait for ExpandingCircleEffect {
             .style(Style::default());
 
         let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("J ", Style::default().fg(Color::Red)),
                 Span::styled(
@@ -0,0 +1,77 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::colour::*;
+use crate::frame_file::CHANNELS;
+use crate::pixel::Pixel;
+
+/// The file after `current` among those in the working directory ending in
+/// `extension`, in name order, wrapping back round to the first
+pub fn next_file(current: &str, extension: &str) -> Option<String> {
+    let entries = fs::read_dir(".").ok()?;
+
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == extension))
+        .filter_map(|path| path.file_name()?.to_str().map(String::from))
+        .collect();
+    paths.sort();
+
+    let next = paths
+        .iter()
+        .position(|path| path == current)
+        .map_or(0, |i| (i + 1) % paths.len());
+
+    paths.into_iter().nth(next)
+}
+
+/// Colours the pixels from packed RGB bytes, leaving any beyond the end of the
+/// data dark
+pub fn show_rgb(data: &[u8], pixels: &mut [Pixel]) {
+    let mut colours = data.chunks_exact(CHANNELS);
+    for pixel in pixels.iter_mut() {
+        pixel.colour = match colours.next() {
+            Some(rgb) => Colour::from(Rgb::from_bytes(rgb[0], rgb[1], rgb[2])),
+            None => BLACK,
+        };
+    }
+}
+
+/// A file being read and decoded on a thread of its own. Effects are updated
+/// with the controller locked, so loading there would hold up every frame and
+/// the interface until the disk caught up
+pub struct Loader<T> {
+    result: Arc<Mutex<Option<Result<T, String>>>>,
+}
+
+impl<T: Send + 'static> Loader<T> {
+    pub fn start<F>(load: F) -> Loader<T>
+    where
+        F: FnOnce() -> Result<T, String> + Send + 'static,
+    {
+        let result = Arc::new(Mutex::new(None));
+        let slot = result.clone();
+        thread::spawn(move || {
+            let loaded = load();
+            *slot.lock().unwrap() = Some(loaded);
+        });
+
+        Loader { result }
+    }
+
+    /// Takes the result once loading has finished
+    pub fn finished(&self) -> Option<Result<T, String>> {
+        self.result.lock().unwrap().take()
+    }
+}
+
+// Deriving would need T to be Clone, when only the shared result is copied
+impl<T> Clone for Loader<T> {
+    fn clone(&self) -> Loader<T> {
+        Loader {
+            result: self.result.clone(),
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+use rand;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::particle::{Particle, ParticleSystem};
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+
+const MAX_PARTICLES: usize = 2000;
+const ROCKET_SPEED: f32 = 250.;
+const ROCKET_RADIUS: f32 = 15.;
+
+#[derive(Clone)]
+pub struct FireworksEffect {
+    rockets: ParticleSystem,
+    sparks: ParticleSystem,
+    launch_timer: f32,
+    launch_rate: f32,
+    burst_size: usize,
+    burst_speed: f32,
+    spark_radius: f32,
+    gravity: f32,
+    drag: f32,
+    fade_time: f32,
+    decay: f32,
+    palette: Palette,
+}
+
+impl FireworksEffect {
+    pub fn default() -> FireworksEffect {
+        FireworksEffect {
+            rockets: ParticleSystem::new(MAX_PARTICLES, 0., 0.),
+            sparks: ParticleSystem::new(MAX_PARTICLES, 0., 0.),
+            launch_timer: 0.,
+            launch_rate: 0.8,
+            burst_size: 60,
+            burst_speed: 150.,
+            spark_radius: 20.,
+            gravity: 120.,
+            drag: 1.5,
+            fade_time: 1.5,
+            decay: 0.7,
+            palette: Palette::rainbow(),
+        }
+    }
+
+    fn launch_rocket(&mut self) {
+        let start = Vec3::new(
+            MIN_X + rand::random::<f32>() * WIDTH,
+            MIN_Y,
+            MIN_Z + rand::random::<f32>() * DEPTH,
+        );
+
+        // Explode somewhere in the top two thirds of the layout
+        let explode_height = HEIGHT / 3. + rand::random::<f32>() * HEIGHT * 2. / 3.;
+        let lifetime = explode_height / ROCKET_SPEED;
+
+        self.rockets.spawn(Particle::new(
+            start,
+            Vec3::new(0., ROCKET_SPEED, 0.),
+            Colour::new(30., 0.3, 1.),
+            lifetime,
+        ));
+    }
+
+    fn explode(&mut self, origin: Vec3) {
+        let colour = self.palette.sample(rand::random::<f32>());
+        self.sparks.spawn_burst(
+            origin,
+            self.burst_size,
+            self.burst_speed,
+            colour,
+            self.fade_time,
+        );
+    }
+}
+
+impl EffectTrait for FireworksEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Fireworks"))
+            .set("palette", self.palette.name())
+            .set("launch_rate", format!("{:1.2}", self.launch_rate))
+            .set("burst_size", format!("{}", self.burst_size))
+            .set("burst_speed", format!("{:3.0}", self.burst_speed))
+            .set("spark_radius", format!("{:3.0}", self.spark_radius))
+            .set("gravity", format!("{:3.0}", self.gravity))
+            .set("drag", format!("{:1.2}", self.drag))
+            .set("fade_time", format!("{:1.2}", self.fade_time))
+            .set("decay", format!("{:1.2}", self.decay));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Fireworks")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(launch_rate) = section.get("launch_rate") {
+                    self.launch_rate = launch_rate.parse().unwrap();
+                }
+
+                if let Some(burst_size) = section.get("burst_size") {
+                    self.burst_size = burst_size.parse().unwrap();
+                }
+
+                if let Some(burst_speed) = section.get("burst_speed") {
+                    self.burst_speed = burst_speed.parse().unwrap();
+                }
+
+                if let Some(spark_radius) = section.get("spark_radius") {
+                    self.spark_radius = spark_radius.parse().unwrap();
+                }
+
+                if let Some(gravity) = section.get("gravity") {
+                    self.gravity = gravity.parse().unwrap();
+                }
+
+                if let Some(drag) = section.get("drag") {
+                    self.drag = drag.parse().unwrap();
+                }
+
+                if let Some(fade_time) = section.get("fade_time") {
+                    self.fade_time = fade_time.parse().unwrap();
+                }
+
+                if let Some(decay) = section.get("decay") {
+                    self.decay = decay.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        self.sparks.gravity = self.gravity;
+        self.sparks.drag = self.drag;
+
+        self.launch_timer += delta * self.launch_rate;
+        while self.launch_timer >= 1. {
+            self.launch_timer -= 1.;
+            self.launch_rocket();
+        }
+
+        // Rockets are killed when they reach their peak, which is where they burst
+        let bursts: Vec<Vec3> = self
+            .rockets
+            .particles()
+            .iter()
+            .filter(|rocket| rocket.age + delta >= rocket.lifetime)
+            .map(|rocket| Vec3::add(rocket.pos, Vec3::mul_scalar(rocket.vel, delta)))
+            .collect();
+
+        self.rockets.update(delta);
+        self.sparks.update(delta);
+
+        for origin in bursts {
+            self.explode(origin);
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        for pixel in pixels.iter_mut() {
+            let mut new_value = pixel.colour.v * self.decay;
+            if new_value < 0.05 {
+                new_value = 0.;
+            }
+
+            let brightest = match (
+                self.rockets.brightest_at(pixel, ROCKET_RADIUS),
+                self.sparks.brightest_at(pixel, self.spark_radius),
+            ) {
+                (Some(rocket), Some(spark)) if spark.v > rocket.v => Some(spark),
+                (Some(rocket), _) => Some(rocket),
+                (None, spark) => spark,
+            };
+
+            match brightest {
+                Some(colour) if colour.v >= new_value => pixel.colour = colour,
+                _ => pixel.colour = Colour::new(pixel.colour.h, pixel.colour.s, new_value),
+            }
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('K') => self.launch_rate = (self.launch_rate + 0.5).clamp(0.1, 10.),
+            KeyCode::Char('k') => self.launch_rate = (self.launch_rate + 0.1).clamp(0.1, 10.),
+            KeyCode::Char('J') => self.launch_rate = (self.launch_rate - 0.5).clamp(0.1, 10.),
+            KeyCode::Char('j') => self.launch_rate = (self.launch_rate - 0.1).clamp(0.1, 10.),
+
+            KeyCode::Up => self.burst_size = (self.burst_size + 10).clamp(10, 300),
+            KeyCode::Down => self.burst_size = (self.burst_size.saturating_sub(10)).clamp(10, 300),
+
+            KeyCode::Char('a') => self.burst_speed = (self.burst_speed - 10.).clamp(10., 500.),
+            KeyCode::Char('s') => self.burst_speed = (self.burst_speed + 10.).clamp(10., 500.),
+
+            KeyCode::Char('z') => self.spark_radius = (self.spark_radius - 5.).clamp(5., 100.),
+            KeyCode::Char('x') => self.spark_radius = (self.spark_radius + 5.).clamp(5., 100.),
+
+            KeyCode::Char('g') => self.gravity = (self.gravity - 10.).clamp(0., 1000.),
+            KeyCode::Char('G') => self.gravity = (self.gravity + 10.).clamp(0., 1000.),
+
+            KeyCode::Char('d') => self.drag = (self.drag - 0.1).clamp(0., 10.),
+            KeyCode::Char('D') => self.drag = (self.drag + 0.1).clamp(0., 10.),
+
+            KeyCode::Char('f') => self.fade_time = (self.fade_time - 0.1).clamp(0.1, 10.),
+            KeyCode::Char('F') => self.fade_time = (self.fade_time + 0.1).clamp(0.1, 10.),
+
+            KeyCode::Char('n') => self.decay = (self.decay - 0.01).clamp(0., 1.),
+            KeyCode::Char('m') => self.decay = (self.decay + 0.01).clamp(0., 1.),
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Launch Rate: {:1.1}/s", self.launch_rate),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Burst Size: {:3}", self.burst_size),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Burst Speed: {:3.0}", self.burst_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Spark Radius: {:3.0}", self.spark_radius),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("g ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Gravity: {:3.0}", self.gravity),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" G", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("d ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Drag: {:1.1}", self.drag),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" D", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("f ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Fade Time: {:1.1}s", self.fade_time),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" F", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Decay: {:1.2}", self.decay),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
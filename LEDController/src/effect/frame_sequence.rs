@@ -0,0 +1,170 @@
+use image::codecs::gif::GifDecoder;
+use image::AnimationDecoder;
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::effect::projection::Texture;
+
+const DEFAULT_FRAME_TIME: f32 = 1. / 24.;
+const MIN_FRAME_TIME: f32 = 0.01;
+
+/// Decoded frames of an animation along with how long each one is shown for
+pub struct FrameSequence {
+    frames: Vec<Texture>,
+    /// Time in seconds at which each frame ends, according to the source
+    end_times: Vec<f32>,
+}
+
+static CACHE: Mutex<Option<HashMap<String, Arc<FrameSequence>>>> = Mutex::new(None);
+
+impl FrameSequence {
+    /// Loads an animated GIF or a directory of numbered frames. Decoding is slow
+    /// so the result is cached for the rest of the session
+    pub fn load_cached(path: &str) -> Result<Arc<FrameSequence>, String> {
+        let mut cache = CACHE.lock().unwrap();
+        let cache = cache.get_or_insert_with(HashMap::new);
+
+        if let Some(sequence) = cache.get(path) {
+            return Ok(sequence.clone());
+        }
+
+        let sequence = Arc::new(FrameSequence::load(path)?);
+        cache.insert(path.to_string(), sequence.clone());
+        Ok(sequence)
+    }
+
+    /// Forgets a cached sequence, so it is decoded again the next time it is loaded
+    pub fn evict(path: &str) {
+        if let Some(cache) = CACHE.lock().unwrap().as_mut() {
+            cache.remove(path);
+        }
+    }
+
+    fn load(path: &str) -> Result<FrameSequence, String> {
+        let sequence = if Path::new(path).is_dir() {
+            FrameSequence::load_directory(path)?
+        } else {
+            FrameSequence::load_gif(path)?
+        };
+
+        if sequence.frames.is_empty() {
+            return Err(format!("No frames found in {}", path));
+        }
+
+        Ok(sequence)
+    }
+
+    fn load_gif(path: &str) -> Result<FrameSequence, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let decoder = GifDecoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+        let frames = decoder
+            .into_frames()
+            .collect_frames()
+            .map_err(|e| e.to_string())?;
+
+        let mut sequence = FrameSequence {
+            frames: Vec::new(),
+            end_times: Vec::new(),
+        };
+
+        let mut time = 0.;
+        for frame in frames {
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay = if denominator == 0 {
+                DEFAULT_FRAME_TIME
+            } else {
+                // Browsers treat tiny GIF delays as the default, so we do the same
+                let delay = numerator as f32 / denominator as f32 / 1000.;
+                if delay < MIN_FRAME_TIME {
+                    DEFAULT_FRAME_TIME
+                } else {
+                    delay
+                }
+            };
+
+            time += delay;
+            let image = image::DynamicImage::ImageRgba8(frame.into_buffer()).to_rgb8();
+            sequence.frames.push(Texture::from_image(&image));
+            sequence.end_times.push(time);
+        }
+
+        Ok(sequence)
+    }
+
+    fn load_directory(path: &str) -> Result<FrameSequence, String> {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .map_err(|e| e.to_string())?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|file| {
+                let extension = file
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+                matches!(extension.as_str(), "png" | "jpg" | "jpeg")
+            })
+            .collect();
+
+        // Sort by the number in the file name so frame_10 comes after frame_9
+        let frame_number = |file: &Path| -> Option<u64> {
+            let name = file.file_stem()?.to_str()?;
+            let digits: String = name
+                .chars()
+                .rev()
+                .skip_while(|c| !c.is_ascii_digit())
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+            digits.chars().rev().collect::<String>().parse().ok()
+        };
+        files.sort_by(|a, b| frame_number(a).cmp(&frame_number(b)).then_with(|| a.cmp(b)));
+
+        let mut sequence = FrameSequence {
+            frames: Vec::new(),
+            end_times: Vec::new(),
+        };
+
+        for (i, file) in files.iter().enumerate() {
+            let image = image::open(file)
+                .map_err(|e| format!("{}: {}", file.display(), e))?
+                .to_rgb8();
+            sequence.frames.push(Texture::from_image(&image));
+            sequence.end_times.push((i + 1) as f32 * DEFAULT_FRAME_TIME);
+        }
+
+        Ok(sequence)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Length of one play through in seconds. A non zero `frame_rate` overrides
+    /// the timing stored in the source
+    pub fn duration(&self, frame_rate: f32) -> f32 {
+        if frame_rate > 0. {
+            self.frames.len() as f32 / frame_rate
+        } else {
+            *self.end_times.last().unwrap_or(&0.)
+        }
+    }
+
+    /// The frame to show `time` seconds into a single play through
+    pub fn frame_at(&self, time: f32, frame_rate: f32) -> &Texture {
+        let index = if frame_rate > 0. {
+            (time * frame_rate) as usize
+        } else {
+            self.end_times.partition_point(|end| *end <= time)
+        };
+
+        &self.frames[index.min(self.frames.len() - 1)]
+    }
+}
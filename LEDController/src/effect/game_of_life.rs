@@ -0,0 +1,373 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+use rand;
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+
+const RULE_PRESETS: [&str; 6] = [
+    "B3/S23",
+    "B36/S23",
+    "B3/S234",
+    "B2/S34",
+    "B35/S2345",
+    "B4/S456",
+];
+
+const MAX_NEIGHBOURS: usize = 16;
+const STALL_GENERATIONS: u32 = 10;
+const AGE_HUE_STEP: f32 = 15.;
+
+#[derive(Copy, Clone, PartialEq)]
+struct Rule {
+    birth: u32,
+    survival: u32,
+}
+
+impl Rule {
+    /// Parses a rule in "B3/S23" notation, where the digits are neighbour counts
+    fn parse(rule: &str) -> Option<Rule> {
+        let mut birth = 0;
+        let mut survival = 0;
+
+        for part in rule.split('/') {
+            let mut chars = part.trim().chars();
+            let mask = match chars.next() {
+                Some('B') | Some('b') => &mut birth,
+                Some('S') | Some('s') => &mut survival,
+                _ => return None,
+            };
+
+            for c in chars {
+                *mask |= 1 << c.to_digit(10)?;
+            }
+        }
+
+        Some(Rule { birth, survival })
+    }
+
+    fn next_state(self, alive: bool, neighbours: usize) -> bool {
+        let mask = if alive { self.survival } else { self.birth };
+        mask & (1 << neighbours) != 0
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |mask: u32| -> String {
+            (0..10)
+                .filter(|n| mask & (1 << n) != 0)
+                .map(|n| n.to_string())
+                .collect()
+        };
+
+        write!(f, "B{}/S{}", digits(self.birth), digits(self.survival))
+    }
+}
+
+/// The per pixel state is shared rather than copied when the effect is cloned
+/// into each rendered frame, each generation replaces it instead
+#[derive(Clone)]
+pub struct GameOfLifeEffect {
+    neighbours: Arc<Vec<Vec<usize>>>,
+    alive: Arc<Vec<bool>>,
+    age: Arc<Vec<u32>>,
+    previous: [Arc<Vec<bool>>; 2],
+    stalled_generations: u32,
+    step_timer: f32,
+    neighbour_count: usize,
+    rule: Rule,
+    step_rate: f32,
+    seed_density: f32,
+    palette: Palette,
+}
+
+impl GameOfLifeEffect {
+    pub fn default() -> GameOfLifeEffect {
+        GameOfLifeEffect {
+            neighbours: Arc::new(Vec::new()),
+            alive: Arc::new(Vec::new()),
+            age: Arc::new(Vec::new()),
+            previous: [Arc::new(Vec::new()), Arc::new(Vec::new())],
+            stalled_generations: 0,
+            step_timer: 0.,
+            neighbour_count: 6,
+            rule: Rule::parse(RULE_PRESETS[0]).unwrap(),
+            step_rate: 4.,
+            seed_density: 0.3,
+            palette: Palette::rainbow(),
+        }
+    }
+
+    fn calculate_neighbours(&mut self, pixels: &[Pixel]) {
+        self.neighbours = Arc::new(
+            pixels
+                .iter()
+                .enumerate()
+                .map(|(i, pixel)| {
+                    let mut others: Vec<(usize, f32)> = pixels
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(j, other)| (j, Vec3::distance(pixel.position, other.position)))
+                        .collect();
+
+                    others.sort_by(|a, b| a.1.total_cmp(&b.1));
+                    others
+                        .iter()
+                        .take(self.neighbour_count)
+                        .map(|(j, _)| *j)
+                        .collect()
+                })
+                .collect(),
+        );
+    }
+
+    fn reseed(&mut self) {
+        self.alive = Arc::new(
+            (0..self.alive.len())
+                .map(|_| rand::random::<f32>() < self.seed_density)
+                .collect(),
+        );
+        self.age = Arc::new(vec![0; self.alive.len()]);
+
+        self.previous = [Arc::new(Vec::new()), Arc::new(Vec::new())];
+        self.stalled_generations = 0;
+    }
+
+    fn step(&mut self) {
+        let next: Arc<Vec<bool>> = Arc::new(
+            (0..self.alive.len())
+                .map(|i| {
+                    let count = self.neighbours[i]
+                        .iter()
+                        .filter(|&&j| self.alive[j])
+                        .count();
+                    self.rule.next_state(self.alive[i], count)
+                })
+                .collect(),
+        );
+
+        self.age = Arc::new(
+            self.age
+                .iter()
+                .zip(next.iter())
+                .map(|(&age, &alive)| if alive { age + 1 } else { 0 })
+                .collect(),
+        );
+
+        // Still lifes and blinkers repeat with a period of one or two generations
+        if self.previous.contains(&next) {
+            self.stalled_generations += 1;
+        } else {
+            self.stalled_generations = 0;
+        }
+
+        let current = std::mem::replace(&mut self.alive, next);
+        self.previous.swap(0, 1);
+        self.previous[0] = current;
+
+        if !self.alive.contains(&true) || self.stalled_generations >= STALL_GENERATIONS {
+            self.reseed();
+        }
+    }
+
+    fn cycle_rule(&mut self) {
+        let current = self.rule.to_string();
+        let index = RULE_PRESETS
+            .iter()
+            .position(|preset| *preset == current)
+            .map_or(0, |i| (i + 1) % RULE_PRESETS.len());
+
+        self.rule = Rule::parse(RULE_PRESETS[index]).unwrap();
+    }
+
+    fn set_neighbour_count(&mut self, neighbour_count: usize) {
+        self.neighbour_count = neighbour_count.clamp(1, MAX_NEIGHBOURS);
+        // Forces the neighbours to be recalculated on the next update
+        self.neighbours = Arc::new(Vec::new());
+    }
+}
+
+impl EffectTrait for GameOfLifeEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.GameOfLife"))
+            .set("palette", self.palette.name())
+            .set("neighbour_count", format!("{}", self.neighbour_count))
+            .set("rule", self.rule.to_string())
+            .set("step_rate", format!("{:1.1}", self.step_rate))
+            .set("seed_density", format!("{:1.2}", self.seed_density));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.GameOfLife")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(neighbour_count) = section.get("neighbour_count") {
+                    self.set_neighbour_count(neighbour_count.parse().unwrap());
+                }
+
+                if let Some(rule) = section.get("rule") {
+                    if let Some(rule) = Rule::parse(rule) {
+                        self.rule = rule;
+                    }
+                }
+
+                if let Some(step_rate) = section.get("step_rate") {
+                    self.step_rate = step_rate.parse::<f32>().unwrap().clamp(0.5, 60.);
+                }
+
+                if let Some(seed_density) = section.get("seed_density") {
+                    self.seed_density = seed_density.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
+        if self.neighbours.len() != pixels.len() {
+            self.calculate_neighbours(pixels);
+        }
+
+        if self.alive.len() != pixels.len() {
+            self.alive = Arc::new(vec![false; pixels.len()]);
+            self.reseed();
+        }
+
+        self.step_timer += delta * self.step_rate;
+        while self.step_timer >= 1. {
+            self.step_timer -= 1.;
+            self.step();
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            pixel.colour = match self.alive.get(i) {
+                Some(true) => self
+                    .palette
+                    .sample((120. + self.age[i] as f32 * AGE_HUE_STEP) / 360.),
+                _ => BLACK,
+            };
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('K') => self.step_rate = (self.step_rate + 1.).clamp(0.5, 60.),
+            KeyCode::Char('k') => self.step_rate = (self.step_rate + 0.5).clamp(0.5, 60.),
+            KeyCode::Char('J') => self.step_rate = (self.step_rate - 1.).clamp(0.5, 60.),
+            KeyCode::Char('j') => self.step_rate = (self.step_rate - 0.5).clamp(0.5, 60.),
+
+            KeyCode::Up => self.set_neighbour_count(self.neighbour_count + 1),
+            KeyCode::Down => self.set_neighbour_count(self.neighbour_count.saturating_sub(1)),
+
+            KeyCode::Char('n') => self.seed_density = (self.seed_density - 0.05).clamp(0.05, 1.),
+            KeyCode::Char('m') => self.seed_density = (self.seed_density + 0.05).clamp(0.05, 1.),
+
+            KeyCode::Char('c') => self.cycle_rule(),
+            KeyCode::Char('s') => self.reseed(),
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Step Rate: {:2.1}/s", self.step_rate),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Neighbours: {:2}", self.neighbour_count),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Seed Density: {:1.2}", self.seed_density),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Rule: {}", self.rule),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("s ", Style::default().fg(Color::Green)),
+                Span::styled("Reseed", Style::default().fg(Color::White)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -0,0 +1,264 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use std::sync::Arc;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::file_playback::Loader;
+use crate::effect::projection::{Projection, Texture};
+use crate::pixel::Pixel;
+
+#[derive(Clone)]
+pub struct ImageProjectionEffect {
+    texture: Option<Arc<Texture>>,
+    error: Option<String>,
+    loading: Option<Loader<Texture>>,
+    /// Set when the path no longer matches the loaded image
+    stale: bool,
+    path: String,
+    projection: Projection,
+    offset_u: f32,
+    offset_v: f32,
+    rotation: f32,
+    scroll_u: f32,
+    scroll_v: f32,
+    rotation_speed: f32,
+}
+
+impl ImageProjectionEffect {
+    pub fn default() -> ImageProjectionEffect {
+        ImageProjectionEffect {
+            texture: None,
+            error: None,
+            loading: None,
+            stale: true,
+            path: String::from("image.png"),
+            projection: Projection::Cylindrical,
+            offset_u: 0.,
+            offset_v: 0.,
+            rotation: 0.,
+            scroll_u: 0.,
+            scroll_v: 0.,
+            rotation_speed: 0.,
+        }
+    }
+
+    /// Loads the image again on the next update, the current one stays until
+    /// the new one is ready
+    fn load_image(&mut self) {
+        self.stale = true;
+    }
+}
+
+impl EffectTrait for ImageProjectionEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.ImageProjection"))
+            .set("path", self.path.clone())
+            .set("projection", format!("{}", self.projection.to_id()))
+            .set("scroll_u", format!("{:1.2}", self.scroll_u))
+            .set("scroll_v", format!("{:1.2}", self.scroll_v))
+            .set("rotation_speed", format!("{:3.0}", self.rotation_speed));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.ImageProjection")) {
+                if let Some(path) = section.get("path") {
+                    self.path = path.to_string();
+                }
+
+                if let Some(projection) = section.get("projection") {
+                    self.projection = Projection::from_id(projection.parse().unwrap());
+                }
+
+                if let Some(scroll_u) = section.get("scroll_u") {
+                    self.scroll_u = scroll_u.parse().unwrap();
+                }
+
+                if let Some(scroll_v) = section.get("scroll_v") {
+                    self.scroll_v = scroll_v.parse().unwrap();
+                }
+
+                if let Some(rotation_speed) = section.get("rotation_speed") {
+                    self.rotation_speed = rotation_speed.parse().unwrap();
+                }
+            }
+        }
+
+        self.load_image();
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        if let Some(result) = self.loading.as_ref().and_then(Loader::finished) {
+            match result {
+                Ok(texture) => {
+                    self.texture = Some(Arc::new(texture));
+                    self.error = None;
+                }
+                Err(err) => {
+                    self.texture = None;
+                    self.error = Some(err);
+                }
+            }
+            self.loading = None;
+        }
+
+        // One load at a time, anything changed meanwhile is loaded once it's done
+        if self.loading.is_none() && self.stale {
+            let path = self.path.clone();
+            self.loading = Some(Loader::start(move || {
+                Texture::load(&path).map_err(|err| err.to_string())
+            }));
+            self.stale = false;
+        }
+
+        self.offset_u = (self.offset_u + self.scroll_u * delta).rem_euclid(1.);
+        self.offset_v = (self.offset_v + self.scroll_v * delta).rem_euclid(1.);
+        self.rotation = (self.rotation + self.rotation_speed * delta).rem_euclid(360.);
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        let Some(texture) = &self.texture else {
+            for pixel in pixels.iter_mut() {
+                pixel.colour = BLACK;
+            }
+            return;
+        };
+
+        for pixel in pixels.iter_mut() {
+            let (u, v) = self.projection.uv(pixel.position, self.rotation);
+            pixel.colour = texture.sample(u + self.offset_u, v + self.offset_v);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('c') => self.projection = self.projection.next(),
+            KeyCode::Char('l') => self.load_image(),
+
+            KeyCode::Char('a') => self.scroll_u = (self.scroll_u - 0.05).clamp(-2., 2.),
+            KeyCode::Char('s') => self.scroll_u = (self.scroll_u + 0.05).clamp(-2., 2.),
+
+            KeyCode::Char('z') => self.scroll_v = (self.scroll_v - 0.05).clamp(-2., 2.),
+            KeyCode::Char('x') => self.scroll_v = (self.scroll_v + 0.05).clamp(-2., 2.),
+
+            KeyCode::Char('J') => {
+                self.rotation_speed = (self.rotation_speed - 10.).clamp(-360., 360.)
+            }
+            KeyCode::Char('j') => {
+                self.rotation_speed = (self.rotation_speed - 5.).clamp(-360., 360.)
+            }
+            KeyCode::Char('K') => {
+                self.rotation_speed = (self.rotation_speed + 10.).clamp(-360., 360.)
+            }
+            KeyCode::Char('k') => {
+                self.rotation_speed = (self.rotation_speed + 5.).clamp(-360., 360.)
+            }
+            _ => {}
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some() || self.stale
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let status = match (&self.texture, &self.error) {
+            _ if self.loading.is_some() => Span::styled(
+                format!("Loading {}", self.path),
+                Style::default().fg(Color::Yellow),
+            ),
+            (Some(texture), _) => Span::styled(
+                format!("{} ({}x{})", self.path, texture.width(), texture.height()),
+                Style::default().fg(Color::Green),
+            ),
+            (None, Some(err)) => Span::styled(
+                format!("{}: {}", self.path, err),
+                Style::default().fg(Color::Red),
+            ),
+            (None, None) => Span::styled(self.path.clone(), Style::default().fg(Color::Red)),
+        };
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("l ", Style::default().fg(Color::Green)),
+                status,
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Projection: {}", self.projection.to_string()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Horizontal Scroll: {:1.2}", self.scroll_u),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Vertical Scroll: {:1.2}", self.scroll_v),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Rotation Speed: {:4.0}", self.rotation_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -0,0 +1,332 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+use rand;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+
+const HEAD_FRACTION: f32 = 0.1;
+
+#[derive(Copy, Clone)]
+struct Drop {
+    angle: f32,
+    head_y: f32,
+    speed: f32,
+    /// How far from the main colour this drop's colour is, from -0.5 to 0.5 of the spread
+    colour_offset: f32,
+}
+
+#[derive(Clone)]
+pub struct MatrixRainEffect {
+    drops: Vec<Drop>,
+    spawn_timer: f32,
+    density: f32,
+    min_speed: f32,
+    max_speed: f32,
+    tail_length: f32,
+    width: f32,
+    palette: Palette,
+    /// Where the drops take their colour from in the palette, from 0 - 1
+    colour_position: f32,
+    /// How much of the palette around `colour_position` the drops are spread over
+    colour_spread: f32,
+}
+
+impl MatrixRainEffect {
+    pub fn default() -> MatrixRainEffect {
+        MatrixRainEffect {
+            drops: Vec::new(),
+            spawn_timer: 0.,
+            density: 6.,
+            min_speed: 80.,
+            max_speed: 200.,
+            tail_length: 120.,
+            width: 25.,
+            palette: Palette::rainbow(),
+            colour_position: 0.35,
+            colour_spread: 0.,
+        }
+    }
+
+    fn spawn_drop(&mut self) {
+        let speed = self.min_speed + rand::random::<f32>() * (self.max_speed - self.min_speed);
+
+        self.drops.push(Drop {
+            angle: rand::random::<f32>() * 360. - 180.,
+            head_y: MAX_Y,
+            speed,
+            colour_offset: rand::random::<f32>() - 0.5,
+        });
+    }
+
+    fn angular_distance(a: f32, b: f32) -> f32 {
+        let difference = (a - b).abs() % 360.;
+        f32::min(difference, 360. - difference)
+    }
+
+    fn intensity(&self, drop: &Drop, angle: f32, y: f32) -> f32 {
+        if MatrixRainEffect::angular_distance(angle, drop.angle) > self.width / 2. {
+            return 0.;
+        }
+
+        let behind_head = y - drop.head_y;
+        if behind_head < 0. || behind_head > self.tail_length {
+            return 0.;
+        }
+
+        1. - behind_head / self.tail_length
+    }
+}
+
+impl EffectTrait for MatrixRainEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.MatrixRain"))
+            .set("density", format!("{:2.1}", self.density))
+            .set("min_speed", format!("{:3.0}", self.min_speed))
+            .set("max_speed", format!("{:3.0}", self.max_speed))
+            .set("tail_length", format!("{:3.0}", self.tail_length))
+            .set("width", format!("{:3.0}", self.width))
+            .set("palette", self.palette.name())
+            .set("colour_position", format!("{:1.2}", self.colour_position))
+            .set("colour_spread", format!("{:1.2}", self.colour_spread));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.MatrixRain")) {
+                if let Some(density) = section.get("density") {
+                    self.density = density.parse().unwrap();
+                }
+
+                if let Some(min_speed) = section.get("min_speed") {
+                    self.min_speed = min_speed.parse().unwrap();
+                }
+
+                if let Some(max_speed) = section.get("max_speed") {
+                    self.max_speed = max_speed.parse().unwrap();
+                }
+
+                if let Some(tail_length) = section.get("tail_length") {
+                    self.tail_length = tail_length.parse().unwrap();
+                }
+
+                if let Some(width) = section.get("width") {
+                    self.width = width.parse().unwrap();
+                }
+
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(colour_position) = section.get("colour_position") {
+                    self.colour_position = colour_position.parse().unwrap();
+                }
+
+                if let Some(colour_spread) = section.get("colour_spread") {
+                    self.colour_spread = colour_spread.parse().unwrap();
+                }
+            }
+        }
+
+        // Keep the speeds in the range the keys allow, so the range is never inverted
+        self.min_speed = self.min_speed.clamp(10., 1000.);
+        self.max_speed = self.max_speed.clamp(self.min_speed, 1000.);
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        self.spawn_timer += delta * self.density;
+        while self.spawn_timer >= 1. {
+            self.spawn_timer -= 1.;
+            self.spawn_drop();
+        }
+
+        for drop in self.drops.iter_mut() {
+            drop.head_y -= drop.speed * delta;
+        }
+
+        let tail_length = self.tail_length;
+        self.drops.retain(|drop| drop.head_y + tail_length >= MIN_Y);
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        for pixel in pixels.iter_mut() {
+            let angle = f32::atan2(pixel.position.z, pixel.position.x).to_degrees();
+
+            // Where drops overlap the brightest one decides the colour
+            let Some((brightest, drop)) = self
+                .drops
+                .iter()
+                .map(|drop| (self.intensity(drop, angle, pixel.position.y), drop))
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+            else {
+                pixel.colour = BLACK;
+                continue;
+            };
+
+            let colour = self
+                .palette
+                .sample(self.colour_position + drop.colour_offset * self.colour_spread);
+
+            // The head of each drop is washed out towards white
+            let saturation = if brightest > 1. - HEAD_FRACTION {
+                colour.s * 0.3
+            } else {
+                colour.s
+            };
+
+            pixel.colour = Colour::new(colour.h, saturation, brightest * colour.v);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('n') => self.density = (self.density - 0.5).clamp(0.5, 50.),
+            KeyCode::Char('m') => self.density = (self.density + 0.5).clamp(0.5, 50.),
+
+            KeyCode::Char('a') => {
+                self.min_speed = (self.min_speed - 10.).clamp(10., self.max_speed)
+            }
+            KeyCode::Char('s') => {
+                self.min_speed = (self.min_speed + 10.).clamp(10., self.max_speed)
+            }
+            KeyCode::Char('z') => {
+                self.max_speed = (self.max_speed - 10.).clamp(self.min_speed, 1000.)
+            }
+            KeyCode::Char('x') => {
+                self.max_speed = (self.max_speed + 10.).clamp(self.min_speed, 1000.)
+            }
+
+            KeyCode::Up => self.tail_length = (self.tail_length + 10.).clamp(10., HEIGHT),
+            KeyCode::Down => self.tail_length = (self.tail_length - 10.).clamp(10., HEIGHT),
+
+            KeyCode::Char('j') => self.width = (self.width - 5.).clamp(5., 180.),
+            KeyCode::Char('k') => self.width = (self.width + 5.).clamp(5., 180.),
+
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+
+            KeyCode::Char('h') => {
+                self.colour_position = (self.colour_position + 0.05).rem_euclid(1.)
+            }
+            KeyCode::Char('H') => {
+                self.colour_position = (self.colour_position - 0.05).rem_euclid(1.)
+            }
+
+            KeyCode::Char('f') => self.colour_spread = (self.colour_spread - 0.1).clamp(0., 1.),
+            KeyCode::Char('g') => self.colour_spread = (self.colour_spread + 0.1).clamp(0., 1.),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Density: {:2.1}/s", self.density),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Min Speed: {:3.0}", self.min_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Max Speed: {:3.0}", self.max_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Tail Length: {:3.0}", self.tail_length),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("j ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Width: {:3.0}", self.width),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" k", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("H ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Colour: {:1.2}", self.colour_position),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" h", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("f ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Colour Spread: {:1.1}", self.colour_spread),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" g", Style::default().fg(Color::Green)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
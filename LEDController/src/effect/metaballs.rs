@@ -0,0 +1,322 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+use rand;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+
+const MAX_BLOBS: usize = 12;
+/// How quickly a blob turns towards a new random direction, in radians per second
+const WANDER_RATE: f32 = 1.5;
+/// Brightness of the liquid around the blobs
+const LIQUID_BRIGHTNESS: f32 = 0.3;
+
+#[derive(Copy, Clone)]
+struct Blob {
+    pos: Vec3,
+    /// Direction of travel, always of unit length
+    direction: Vec3,
+}
+
+impl Blob {
+    fn random() -> Blob {
+        Blob {
+            pos: Vec3::new(
+                MIN_X + rand::random::<f32>() * WIDTH,
+                MIN_Y + rand::random::<f32>() * HEIGHT,
+                MIN_Z + rand::random::<f32>() * DEPTH,
+            ),
+            direction: Blob::random_direction(),
+        }
+    }
+
+    fn random_direction() -> Vec3 {
+        Vec3::norm(Vec3::new(
+            rand::random::<f32>() - 0.5,
+            rand::random::<f32>() - 0.5,
+            rand::random::<f32>() - 0.5,
+        ))
+    }
+
+    /// Drifts along the current direction, slowly turning so the paths look organic
+    fn update(&mut self, delta: f32, speed: f32) {
+        let turn = Vec3::mul_scalar(Blob::random_direction(), WANDER_RATE * delta);
+        self.direction = Vec3::norm(Vec3::add(self.direction, turn));
+
+        self.pos = Vec3::add(self.pos, Vec3::mul_scalar(self.direction, speed * delta));
+
+        let bounce = |pos: &mut f32, dir: &mut f32, min: f32, max: f32| {
+            if *pos < min {
+                *pos = min;
+                *dir = dir.abs();
+            } else if *pos > max {
+                *pos = max;
+                *dir = -dir.abs();
+            }
+        };
+
+        bounce(&mut self.pos.x, &mut self.direction.x, MIN_X, MAX_X);
+        bounce(&mut self.pos.y, &mut self.direction.y, MIN_Y, MAX_Y);
+        bounce(&mut self.pos.z, &mut self.direction.z, MIN_Z, MAX_Z);
+    }
+}
+
+#[derive(Clone)]
+pub struct MetaballsEffect {
+    blobs: Vec<Blob>,
+    blob_count: usize,
+    size: f32,
+    speed: f32,
+    softness: f32,
+    palette: Palette,
+    /// Where the blobs and the liquid take their colours from in the palette, from 0 - 1
+    blob_position: f32,
+    liquid_position: f32,
+}
+
+impl MetaballsEffect {
+    pub fn default() -> MetaballsEffect {
+        MetaballsEffect {
+            blobs: Vec::new(),
+            blob_count: 5,
+            size: 50.,
+            speed: 30.,
+            softness: 0.3,
+            palette: Palette::rainbow(),
+            blob_position: 0.05,
+            liquid_position: 0.8,
+        }
+    }
+
+    /// Sum of every blob's field at a point, which crosses 1 at the surface of a lone blob
+    fn field(&self, position: Vec3) -> f32 {
+        let size_squared = self.size * self.size;
+        self.blobs
+            .iter()
+            .map(|blob| {
+                let offset = Vec3::sub(position, blob.pos);
+                let distance_squared = Vec3::dot(offset, offset);
+                size_squared / distance_squared.max(1.)
+            })
+            .sum()
+    }
+
+    /// How far inside the blobs a point is, 0 in the liquid and 1 inside a blob,
+    /// blended over the softness either side of the surface
+    fn coverage(&self, field: f32) -> f32 {
+        if self.softness <= 0. {
+            return if field >= 1. { 1. } else { 0. };
+        }
+
+        let t = ((field - (1. - self.softness)) / (2. * self.softness)).clamp(0., 1.);
+        t * t * (3. - 2. * t)
+    }
+}
+
+impl EffectTrait for MetaballsEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Metaballs"))
+            .set("blob_count", format!("{}", self.blob_count))
+            .set("size", format!("{:3.0}", self.size))
+            .set("speed", format!("{:3.0}", self.speed))
+            .set("softness", format!("{:1.2}", self.softness))
+            .set("palette", self.palette.name())
+            .set("blob_position", format!("{:1.2}", self.blob_position))
+            .set("liquid_position", format!("{:1.2}", self.liquid_position));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Metaballs")) {
+                if let Some(blob_count) = section.get("blob_count") {
+                    self.blob_count = blob_count.parse::<usize>().unwrap().clamp(1, MAX_BLOBS);
+                }
+
+                if let Some(size) = section.get("size") {
+                    self.size = size.parse().unwrap();
+                }
+
+                if let Some(speed) = section.get("speed") {
+                    self.speed = speed.parse().unwrap();
+                }
+
+                if let Some(softness) = section.get("softness") {
+                    self.softness = softness.parse().unwrap();
+                }
+
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(blob_position) = section.get("blob_position") {
+                    self.blob_position = blob_position.parse().unwrap();
+                }
+
+                if let Some(liquid_position) = section.get("liquid_position") {
+                    self.liquid_position = liquid_position.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        self.blobs.truncate(self.blob_count);
+        while self.blobs.len() < self.blob_count {
+            self.blobs.push(Blob::random());
+        }
+
+        for blob in self.blobs.iter_mut() {
+            blob.update(delta, self.speed);
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        let blob_colour = self.palette.sample(self.blob_position);
+        let liquid = self.palette.sample(self.liquid_position);
+        let liquid_colour = Colour::new(liquid.h, liquid.s, liquid.v * LIQUID_BRIGHTNESS);
+
+        for pixel in pixels.iter_mut() {
+            let coverage = self.coverage(self.field(pixel.position));
+            pixel.colour = Colour::blend(liquid_colour, blob_colour, coverage);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Up => self.blob_count = (self.blob_count + 1).clamp(1, MAX_BLOBS),
+            KeyCode::Down => {
+                self.blob_count = self.blob_count.saturating_sub(1).clamp(1, MAX_BLOBS)
+            }
+
+            KeyCode::Char('a') => self.size = (self.size - 5.).clamp(5., 200.),
+            KeyCode::Char('s') => self.size = (self.size + 5.).clamp(5., 200.),
+
+            KeyCode::Char('J') => self.speed = (self.speed - 10.).clamp(0., 300.),
+            KeyCode::Char('j') => self.speed = (self.speed - 2.).clamp(0., 300.),
+            KeyCode::Char('K') => self.speed = (self.speed + 10.).clamp(0., 300.),
+            KeyCode::Char('k') => self.speed = (self.speed + 2.).clamp(0., 300.),
+
+            KeyCode::Char('z') => self.softness = (self.softness - 0.05).clamp(0., 1.),
+            KeyCode::Char('x') => self.softness = (self.softness + 0.05).clamp(0., 1.),
+
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+
+            KeyCode::Char('n') => self.blob_position = (self.blob_position - 0.05).rem_euclid(1.),
+            KeyCode::Char('m') => self.blob_position = (self.blob_position + 0.05).rem_euclid(1.),
+
+            KeyCode::Char('f') => {
+                self.liquid_position = (self.liquid_position - 0.05).rem_euclid(1.)
+            }
+            KeyCode::Char('g') => {
+                self.liquid_position = (self.liquid_position + 0.05).rem_euclid(1.)
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("Down ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Blob Count: {}", self.blob_count),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" Up", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Size: {:3.0}", self.size),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Speed: {:3.0}", self.speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Softness: {:1.2}", self.softness),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Blob Colour: {:1.2}", self.blob_position),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("f ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Liquid Colour: {:1.2}", self.liquid_position),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" g", Style::default().fg(Color::Green)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -1,7 +1,24 @@
+pub mod animation;
+pub mod bouncing_balls;
 pub mod constants;
 pub mod effect_list;
 pub mod effect_trait;
 pub mod expanding_circle;
+pub mod file_playback;
+pub mod fireworks;
+pub mod font;
+pub mod frame_sequence;
+pub mod game_of_life;
+pub mod image_projection;
+pub mod matrix_rain;
+pub mod metaballs;
+pub mod particle;
+pub mod playback;
+pub mod projection;
 pub mod rainbow_plane;
 pub mod random_moving_plane;
+pub mod scrolling_text;
+pub mod sequence;
 pub mod solid_colour;
+pub mod spectrum;
+pub mod strand_chase;
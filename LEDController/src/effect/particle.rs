@@ -0,0 +1,140 @@
+use rand;
+
+use crate::colour::*;
+use crate::pixel::Pixel;
+use crate::vec3::Vec3;
+
+#[derive(Copy, Clone)]
+pub struct Particle {
+    pub pos: Vec3,
+    pub vel: Vec3,
+    pub colour: Colour,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    pub fn new(pos: Vec3, vel: Vec3, colour: Colour, lifetime: f32) -> Particle {
+        Particle {
+            pos,
+            vel,
+            colour,
+            age: 0.,
+            lifetime,
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+
+    /// Fraction of the lifetime left, going from 1 when spawned down to 0
+    pub fn life_remaining(&self) -> f32 {
+        (1. - self.age / self.lifetime).clamp(0., 1.)
+    }
+}
+
+#[derive(Clone)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    max_particles: usize,
+    pub gravity: f32,
+    pub drag: f32,
+}
+
+impl ParticleSystem {
+    pub fn new(max_particles: usize, gravity: f32, drag: f32) -> ParticleSystem {
+        ParticleSystem {
+            particles: Vec::new(),
+            max_particles,
+            gravity,
+            drag,
+        }
+    }
+
+    pub fn particles(&self) -> &Vec<Particle> {
+        &self.particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn spawn(&mut self, particle: Particle) {
+        if self.particles.len() < self.max_particles {
+            self.particles.push(particle);
+        }
+    }
+
+    /// Spawns `count` particles from `origin` in uniformly random directions
+    pub fn spawn_burst(
+        &mut self,
+        origin: Vec3,
+        count: usize,
+        speed: f32,
+        colour: Colour,
+        lifetime: f32,
+    ) {
+        for _ in 0..count {
+            let theta = rand::random::<f32>() * 2. * std::f32::consts::PI;
+            let cos_phi = rand::random::<f32>() * 2. - 1.;
+            let sin_phi = f32::sqrt(1. - cos_phi * cos_phi);
+            let direction = Vec3::new(
+                sin_phi * f32::cos(theta),
+                cos_phi,
+                sin_phi * f32::sin(theta),
+            );
+
+            // Vary the speed and lifetime a little so the burst doesn't look like a shell
+            let particle_speed = speed * (0.7 + rand::random::<f32>() * 0.3);
+            let particle_lifetime = lifetime * (0.75 + rand::random::<f32>() * 0.5);
+
+            self.spawn(Particle::new(
+                origin,
+                Vec3::mul_scalar(direction, particle_speed),
+                colour,
+                particle_lifetime,
+            ));
+        }
+    }
+
+    /// Integrates every particle by `delta` seconds and removes the ones that have died
+    pub fn update(&mut self, delta: f32) {
+        let gravity = Vec3::new(0., -self.gravity * delta, 0.);
+        let drag = f32::exp(-self.drag * delta);
+
+        for particle in self.particles.iter_mut() {
+            particle.vel = Vec3::mul_scalar(Vec3::add(particle.vel, gravity), drag);
+            particle.pos = Vec3::add(particle.pos, Vec3::mul_scalar(particle.vel, delta));
+            particle.age += delta;
+        }
+
+        self.particles.retain(|particle| particle.is_alive());
+    }
+
+    /// Returns the colour of the brightest particle within `radius` of the pixel,
+    /// with the value scaled by distance and remaining life
+    pub fn brightest_at(&self, pixel: &Pixel, radius: f32) -> Option<Colour> {
+        let mut brightest: Option<Colour> = None;
+
+        for particle in self.particles.iter() {
+            let distance = Vec3::distance(pixel.position, particle.pos);
+            if distance >= radius {
+                continue;
+            }
+
+            let t = 1. - distance / radius;
+            let intensity = t * t * (3. - 2. * t) * particle.life_remaining() * particle.colour.v;
+
+            if intensity > brightest.map_or(0., |c| c.v) {
+                brightest = Some(Colour::new(particle.colour.h, particle.colour.s, intensity));
+            }
+        }
+
+        brightest
+    }
+}
@@ -0,0 +1,249 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use std::sync::Arc;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::animation::LoopMode;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::file_playback::{self, Loader};
+use crate::frame_file::FrameFile;
+use crate::pixel::Pixel;
+
+const EXTENSION: &str = "ledf";
+
+/// Replays a recorded session or an offline render, pixel by pixel
+#[derive(Clone)]
+pub struct PlaybackEffect {
+    file: Option<Arc<FrameFile>>,
+    error: Option<String>,
+    loading: Option<Loader<FrameFile>>,
+    /// Set when the path no longer matches the loaded file
+    stale: bool,
+    path: String,
+    time: f32,
+    loop_mode: LoopMode,
+    playback_speed: f32,
+}
+
+impl PlaybackEffect {
+    pub fn default() -> PlaybackEffect {
+        PlaybackEffect {
+            file: None,
+            error: None,
+            loading: None,
+            stale: true,
+            path: String::from("recording.ledf"),
+            time: 0.,
+            loop_mode: LoopMode::Loop,
+            playback_speed: 1.,
+        }
+    }
+
+    /// Loads the file again on the next update, the current one keeps playing
+    /// until the new one is ready
+    fn load_file(&mut self) {
+        self.stale = true;
+    }
+
+    /// Moves on to the next frame file in the working directory, in name order
+    fn next_file(&mut self) {
+        if let Some(path) = file_playback::next_file(&self.path, EXTENSION) {
+            self.path = path;
+            self.load_file();
+        }
+    }
+}
+
+impl EffectTrait for PlaybackEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Playback"))
+            .set("path", self.path.clone())
+            .set("loop_mode", format!("{}", self.loop_mode.to_id()))
+            .set("playback_speed", format!("{:1.2}", self.playback_speed));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Playback")) {
+                if let Some(path) = section.get("path") {
+                    self.path = path.to_string();
+                }
+
+                if let Some(loop_mode) = section.get("loop_mode") {
+                    self.loop_mode = LoopMode::from_id(loop_mode.parse().unwrap());
+                }
+
+                if let Some(playback_speed) = section.get("playback_speed") {
+                    self.playback_speed = playback_speed.parse().unwrap();
+                }
+            }
+        }
+
+        self.load_file();
+    }
+
+    fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
+        if let Some(result) = self.loading.as_ref().and_then(Loader::finished) {
+            match result {
+                Ok(file) => {
+                    self.file = Some(Arc::new(file));
+                    self.error = None;
+                }
+                Err(err) => {
+                    self.file = None;
+                    self.error = Some(err);
+                }
+            }
+            self.loading = None;
+            self.time = 0.;
+        }
+
+        // One load at a time, anything changed meanwhile is loaded once it's done
+        if self.loading.is_none() && self.stale {
+            let path = self.path.clone();
+            self.loading = Some(Loader::start(move || FrameFile::load(&path)));
+            self.stale = false;
+        }
+
+        self.time += delta * self.playback_speed;
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        let Some(file) = &self.file else {
+            for pixel in pixels.iter_mut() {
+                pixel.colour = BLACK;
+            }
+            return;
+        };
+
+        let time = self.loop_mode.position(self.time, file.duration());
+        let frame = file.frame_at(time);
+
+        file_playback::show_rgb(frame, pixels);
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('c') => self.next_file(),
+            KeyCode::Char('b') => self.loop_mode = self.loop_mode.next(),
+            KeyCode::Char('l') => self.load_file(),
+            KeyCode::Char('s') => self.time = 0.,
+
+            KeyCode::Char('J') => self.playback_speed = (self.playback_speed - 0.5).clamp(-4., 4.),
+            KeyCode::Char('j') => self.playback_speed = (self.playback_speed - 0.1).clamp(-4., 4.),
+            KeyCode::Char('K') => self.playback_speed = (self.playback_speed + 0.5).clamp(-4., 4.),
+            KeyCode::Char('k') => self.playback_speed = (self.playback_speed + 0.1).clamp(-4., 4.),
+            _ => {}
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some() || self.stale
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let status = match (&self.file, &self.error) {
+            _ if self.loading.is_some() => Span::styled(
+                format!("Loading {}", self.path),
+                Style::default().fg(Color::Yellow),
+            ),
+            (Some(file), _) => Span::styled(
+                format!(
+                    "{} ({} frames, {:.1}s)",
+                    self.path,
+                    file.frame_count(),
+                    file.duration()
+                ),
+                Style::default().fg(Color::Green),
+            ),
+            (None, Some(err)) => Span::styled(err.clone(), Style::default().fg(Color::Red)),
+            (None, None) => Span::styled(self.path.clone(), Style::default().fg(Color::Red)),
+        };
+
+        let position = match &self.file {
+            Some(file) => format!(
+                "{:.1}s / {:.1}s",
+                self.loop_mode.position(self.time, file.duration()),
+                file.duration()
+            ),
+            None => String::from("-"),
+        };
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("l ", Style::default().fg(Color::Green)),
+                status,
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled("Next Recording", Style::default().fg(Color::White)),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Loop Mode: {}", self.loop_mode.to_string()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Playback Speed: {:1.1}x", self.playback_speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("s ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Restart ({})", position),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
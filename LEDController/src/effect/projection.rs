@@ -0,0 +1,133 @@
+use image::RgbImage;
+
+use crate::colour::*;
+use crate::effect::constants::*;
+use crate::vec3::Vec3;
+
+/// How a 2D image is wrapped onto the 3D layout
+#[derive(Copy, Clone, PartialEq)]
+pub enum Projection {
+    /// Straight through the layout from the front, along the Z axis
+    PlanarZ,
+    /// Straight through the layout from the side, along the X axis
+    PlanarX,
+    /// Straight down through the layout from the top, along the Y axis
+    PlanarY,
+    /// Wrapped around the Y axis like a label on a can
+    Cylindrical,
+    /// Wrapped around the centre of the layout like a globe
+    Spherical,
+}
+
+impl Projection {
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Projection::PlanarZ => "Planar Front",
+            Projection::PlanarX => "Planar Side",
+            Projection::PlanarY => "Planar Top",
+            Projection::Cylindrical => "Cylindrical",
+            Projection::Spherical => "Spherical",
+        }
+    }
+
+    pub fn to_id(self) -> u32 {
+        match self {
+            Projection::PlanarZ => 0,
+            Projection::PlanarX => 1,
+            Projection::PlanarY => 2,
+            Projection::Cylindrical => 3,
+            Projection::Spherical => 4,
+        }
+    }
+
+    pub fn from_id(id: u32) -> Projection {
+        match id {
+            1 => Projection::PlanarX,
+            2 => Projection::PlanarY,
+            3 => Projection::Cylindrical,
+            4 => Projection::Spherical,
+            _ => Projection::PlanarZ,
+        }
+    }
+
+    pub fn next(self) -> Projection {
+        Projection::from_id((self.to_id() + 1) % 5)
+    }
+
+    /// Maps a position to image coordinates, where (0, 0) is the top left of the
+    /// image and (1, 1) the bottom right. `rotation` turns the layout around the
+    /// Y axis first, in degrees
+    pub fn uv(self, position: Vec3, rotation: f32) -> (f32, f32) {
+        let (sin, cos) = rotation.to_radians().sin_cos();
+        let p = Vec3::new(
+            position.x * cos - position.z * sin,
+            position.y,
+            position.x * sin + position.z * cos,
+        );
+
+        let height = 1. - (p.y - MIN_Y) / HEIGHT;
+        let around = f32::atan2(p.z, p.x) / (2. * std::f32::consts::PI) + 0.5;
+
+        match self {
+            Projection::PlanarZ => ((p.x - MIN_X) / WIDTH, height),
+            Projection::PlanarX => ((p.z - MIN_Z) / DEPTH, height),
+            Projection::PlanarY => ((p.x - MIN_X) / WIDTH, (p.z - MIN_Z) / DEPTH),
+            Projection::Cylindrical => (around, height),
+            Projection::Spherical => {
+                let centre = Vec3::new(0., MIN_Y + HEIGHT / 2., 0.);
+                let offset = Vec3::sub(p, centre);
+                let distance = Vec3::mag(offset);
+                let polar = if distance == 0. {
+                    0.5
+                } else {
+                    f32::acos((offset.y / distance).clamp(-1., 1.)) / std::f32::consts::PI
+                };
+                (around, polar)
+            }
+        }
+    }
+}
+
+/// An image decoded once into colours, ready to be sampled per pixel
+pub struct Texture {
+    width: u32,
+    height: u32,
+    colours: Vec<Colour>,
+}
+
+impl Texture {
+    pub fn from_image(image: &RgbImage) -> Texture {
+        Texture {
+            width: image.width(),
+            height: image.height(),
+            colours: image
+                .pixels()
+                .map(|p| Colour::from_rgb(p[0], p[1], p[2]))
+                .collect(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Texture, image::ImageError> {
+        Ok(Texture::from_image(&image::open(path)?.to_rgb8()))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Nearest neighbour lookup, with coordinates outside 0 - 1 wrapping around
+    pub fn sample(&self, u: f32, v: f32) -> Colour {
+        if self.colours.is_empty() {
+            return BLACK;
+        }
+
+        let x = ((u.rem_euclid(1.) * self.width as f32) as u32).min(self.width - 1);
+        let y = ((v.rem_euclid(1.) * self.height as f32) as u32).min(self.height - 1);
+
+        self.colours[(y * self.width + x) as usize]
+    }
+}
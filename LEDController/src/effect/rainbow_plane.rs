@@ -9,16 +9,21 @@ use ratatui::{
 
 use ini::Ini;
 
-use crate::colour::*;
+use crate::config;
 use crate::effect::{constants::CONFIG_NAME, effect_trait::EffectTrait};
+use crate::palette::Palette;
 use crate::pixel::Pixel;
+use crate::tempo;
 use crate::vec3::Vec3;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct RainbowPlaneEffect {
     pos: Vec3,
     multiplier: u32,
     movement_speed: f32,
+    tempo_sync: bool,
+    beats_per_cycle: f32,
+    palette: Palette,
 }
 
 impl RainbowPlaneEffect {
@@ -27,11 +32,18 @@ impl RainbowPlaneEffect {
             pos: Vec3::new(0., 0., 0.),
             multiplier: 1,
             movement_speed: 50.,
+            tempo_sync: false,
+            beats_per_cycle: 4.,
+            palette: Palette::rainbow(),
         }
     }
 }
 
 impl EffectTrait for RainbowPlaneEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
     fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
         self
     }
@@ -44,21 +56,34 @@ impl EffectTrait for RainbowPlaneEffect {
 
         config
             .with_section(Some("Effect.RainbowPlane"))
+            .set("palette", self.palette.name())
             .set("multiplier", format!("{}", self.multiplier))
-            .set("movement_speed", format!("{:3.0}", self.movement_speed));
+            .set("movement_speed", format!("{:3.0}", self.movement_speed))
+            .set("tempo_sync", format!("{}", self.tempo_sync))
+            .set("beats_per_cycle", format!("{}", self.beats_per_cycle));
 
-        config.write_to_file(CONFIG_NAME).unwrap();
+        config::save(&config);
     }
 
     fn read_settings(&mut self) {
         if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
             if let Some(section) = config.section(Some("Effect.RainbowPlane")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
                 if let Some(multiplier) = section.get("multiplier") {
                     self.multiplier = multiplier.parse().unwrap();
                 }
                 if let Some(movement_speed) = section.get("movement_speed") {
                     self.movement_speed = movement_speed.parse().unwrap();
                 }
+                if let Some(tempo_sync) = section.get("tempo_sync") {
+                    self.tempo_sync = tempo_sync.parse().unwrap();
+                }
+                if let Some(beats_per_cycle) = section.get("beats_per_cycle") {
+                    self.beats_per_cycle = beats_per_cycle.parse::<f32>().unwrap().clamp(0.25, 64.);
+                }
             }
         }
     }
@@ -66,7 +91,14 @@ impl EffectTrait for RainbowPlaneEffect {
     fn update(&mut self, delta: f32, _pixels: &Vec<Pixel>) {
         let normal = Vec3::new(0., 1., 0.);
 
-        let movement = self.movement_speed * delta;
+        // The hue repeats every 360 units, so one cycle of the rainbow is 360 units of movement
+        let speed = if self.tempo_sync {
+            tempo::speed_for(360., self.beats_per_cycle)
+        } else {
+            self.movement_speed
+        };
+
+        let movement = speed * delta;
         let mut new_pos = Vec3::new(
             self.pos.x + normal.x * movement,
             self.pos.y + normal.y * movement,
@@ -86,7 +118,7 @@ impl EffectTrait for RainbowPlaneEffect {
                 (self.multiplier as f32)
                     * (Vec3::dot(new_position, normal).abs() / Vec3::mag(normal)),
             );
-            pixel.colour = Colour::new(distance + 30., 1., 1.);
+            pixel.colour = self.palette.sample((distance + 30.) / 360.);
         }
     }
 
@@ -100,8 +132,16 @@ impl EffectTrait for RainbowPlaneEffect {
                 self.movement_speed = (self.movement_speed - 10.).clamp(0., 1000.)
             }
             KeyCode::Char('j') => self.movement_speed = (self.movement_speed - 5.).clamp(0., 1000.),
+            KeyCode::Char('b') => self.tempo_sync = !self.tempo_sync,
+            KeyCode::Char('n') => {
+                self.beats_per_cycle = (self.beats_per_cycle / 2.).clamp(0.25, 64.)
+            }
+            KeyCode::Char('m') => {
+                self.beats_per_cycle = (self.beats_per_cycle * 2.).clamp(0.25, 64.)
+            }
             KeyCode::Up => self.multiplier = (self.multiplier + 1).clamp(1, 10),
             KeyCode::Down => self.multiplier = (self.multiplier - 1).clamp(1, 10),
+            KeyCode::Char('p') => self.palette = self.palette.next(),
             _ => {}
         }
     }
@@ -121,6 +161,13 @@ impl EffectTrait for RainbowPlaneEffect {
             .style(Style::default());
 
         let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("J ", Style::default().fg(Color::Red)),
                 Span::styled(
@@ -137,6 +184,21 @@ impl EffectTrait for RainbowPlaneEffect {
                 ),
                 Span::styled(" <up>", Style::default().fg(Color::Green)),
             ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Tempo Sync: {}", if self.tempo_sync { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Beats per Cycle: {}", self.beats_per_cycle),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
         ])
         .centered()
         .block(block);
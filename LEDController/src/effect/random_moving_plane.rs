@@ -12,12 +12,15 @@ use rand;
 use ini::Ini;
 
 use crate::colour::*;
+use crate::config;
 use crate::effect::constants::*;
 use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
 use crate::pixel::Pixel;
+use crate::tempo;
 use crate::vec3::Vec3;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct RandomMovingPlaneEffect {
     pos: Vec3,
     normal: Vec3,
@@ -25,6 +28,10 @@ pub struct RandomMovingPlaneEffect {
     movement_speed: f32,
     decay: f32,
     distance: f32,
+    palette: Palette,
+    palette_position: f32,
+    tempo_sync: bool,
+    beats_per_cycle: f32,
 }
 
 impl RandomMovingPlaneEffect {
@@ -36,6 +43,10 @@ impl RandomMovingPlaneEffect {
             distance: 30.,
             decay: 0.9,
             colour: WHITE,
+            palette: Palette::rainbow(),
+            palette_position: 0.,
+            tempo_sync: false,
+            beats_per_cycle: 4.,
         };
 
         eff.random_pos();
@@ -57,6 +68,11 @@ impl RandomMovingPlaneEffect {
         self.pos = Vec3::new(x, y, z);
     }
 
+    /// Distance the plane travels from one side of the tree to the other
+    fn pass_length(&self) -> f32 {
+        2. * (f32::max(WIDTH, DEPTH) + self.distance)
+    }
+
     fn recalculate_normal(&mut self) {
         let center = Vec3::new(0., HEIGHT / 2., 0.);
 
@@ -64,8 +80,9 @@ impl RandomMovingPlaneEffect {
     }
 
     fn random_colour(&mut self) {
-        let hue = (self.colour.h + (60. + f32::round(rand::random::<f32>() * 240.))) % 360.;
-        self.colour = Colour::new(hue, 1., 1.);
+        let step = (60. + f32::round(rand::random::<f32>() * 240.)) / 360.;
+        self.palette_position = (self.palette_position + step) % 1.;
+        self.colour = self.palette.sample(self.palette_position);
     }
 
     fn should_be_coloured(&self, pixel: &Pixel) -> Result<f32, ()> {
@@ -81,6 +98,10 @@ impl RandomMovingPlaneEffect {
 }
 
 impl EffectTrait for RandomMovingPlaneEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
     fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
         self
     }
@@ -93,16 +114,23 @@ impl EffectTrait for RandomMovingPlaneEffect {
 
         config
             .with_section(Some("Effect.RandomMovingPlane"))
+            .set("palette", self.palette.name())
             .set("movement_speed", format!("{:3.0}", self.movement_speed))
             .set("decay", format!("{:1.2}", self.decay))
-            .set("distance", format!("{:3.0}", self.distance));
+            .set("distance", format!("{:3.0}", self.distance))
+            .set("tempo_sync", format!("{}", self.tempo_sync))
+            .set("beats_per_cycle", format!("{}", self.beats_per_cycle));
 
-        config.write_to_file(CONFIG_NAME).unwrap();
+        config::save(&config);
     }
 
     fn read_settings(&mut self) {
         if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
             if let Some(section) = config.section(Some("Effect.RandomMovingPlane")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
                 if let Some(movement_speed) = section.get("movement_speed") {
                     self.movement_speed = movement_speed.parse().unwrap();
                 }
@@ -114,12 +142,26 @@ impl EffectTrait for RandomMovingPlaneEffect {
                 if let Some(distance) = section.get("distance") {
                     self.distance = distance.parse().unwrap();
                 }
+
+                if let Some(tempo_sync) = section.get("tempo_sync") {
+                    self.tempo_sync = tempo_sync.parse().unwrap();
+                }
+
+                if let Some(beats_per_cycle) = section.get("beats_per_cycle") {
+                    self.beats_per_cycle = beats_per_cycle.parse::<f32>().unwrap().clamp(0.25, 64.);
+                }
             }
         }
     }
 
     fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
-        let movement = self.movement_speed * delta;
+        let speed = if self.tempo_sync {
+            tempo::speed_for(self.pass_length(), self.beats_per_cycle)
+        } else {
+            self.movement_speed
+        };
+
+        let movement = speed * delta;
         let new_pos = Vec3::new(
             self.pos.x + self.normal.x * movement,
             self.pos.y + self.normal.y * movement,
@@ -151,11 +193,7 @@ impl EffectTrait for RandomMovingPlaneEffect {
     fn render(&self, pixels: &mut Vec<Pixel>) {
         for pixel in pixels.iter_mut() {
             if let Ok(_) = self.should_be_coloured(pixel) {
-                pixel.colour = Colour {
-                    h: self.colour.h,
-                    s: 1.,
-                    v: 1.,
-                };
+                pixel.colour = self.colour;
             } else {
                 let mut new_value = pixel.colour.v * self.decay;
                 if new_value < 0.1 {
@@ -185,8 +223,17 @@ impl EffectTrait for RandomMovingPlaneEffect {
             KeyCode::Char('n') => self.decay = (self.decay - 0.01).clamp(0., 1.),
             KeyCode::Char('m') => self.decay = (self.decay + 0.01).clamp(0., 1.),
 
+            KeyCode::Char('b') => self.tempo_sync = !self.tempo_sync,
+            KeyCode::Char('c') => {
+                self.beats_per_cycle = (self.beats_per_cycle / 2.).clamp(0.25, 64.)
+            }
+            KeyCode::Char('v') => {
+                self.beats_per_cycle = (self.beats_per_cycle * 2.).clamp(0.25, 64.)
+            }
+
             KeyCode::Up => self.distance = (self.distance + 1.).clamp(1., 200.),
             KeyCode::Down => self.distance = (self.distance - 1.).clamp(1., 200.),
+            KeyCode::Char('p') => self.palette = self.palette.next(),
             _ => {}
         }
     }
@@ -206,6 +253,13 @@ impl EffectTrait for RandomMovingPlaneEffect {
             .style(Style::default());
 
         let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
             Line::from(vec![
                 Span::styled("J ", Style::default().fg(Color::Red)),
                 Span::styled(
@@ -230,6 +284,21 @@ impl EffectTrait for RandomMovingPlaneEffect {
                 ),
                 Span::styled(" m", Style::default().fg(Color::Green)),
             ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Tempo Sync: {}", if self.tempo_sync { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Beats per Cycle: {}", self.beats_per_cycle),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" v", Style::default().fg(Color::Green)),
+            ]),
         ])
         .centered()
         .block(block);
@@ -0,0 +1,359 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+
+const MAX_TEXT_LENGTH: usize = 64;
+/// Blank columns between the end of the message and the start of the next repeat
+const MESSAGE_GAP: usize = GLYPH_WIDTH * 2;
+
+#[derive(Clone)]
+pub struct ScrollingTextEffect {
+    text: String,
+    /// Text being typed in the TUI, replacing `text` once confirmed
+    editing: Option<String>,
+    columns: Vec<u8>,
+    offset: f32,
+    /// Radius of the layout around the text, used to keep the letters in proportion
+    band_radius: f32,
+    speed: f32,
+    font_size: f32,
+    height: f32,
+    palette: Palette,
+    /// Where the text and the background take their colours from in the palette, from 0 - 1
+    text_position: f32,
+    background: f32,
+    background_position: f32,
+}
+
+impl ScrollingTextEffect {
+    pub fn default() -> ScrollingTextEffect {
+        let mut effect = ScrollingTextEffect {
+            text: String::new(),
+            editing: None,
+            columns: Vec::new(),
+            offset: 0.,
+            band_radius: WIDTH / 4.,
+            speed: 10.,
+            font_size: 70.,
+            height: MIN_Y + HEIGHT / 2.,
+            palette: Palette::rainbow(),
+            text_position: 0.,
+            background: 0.,
+            background_position: 0.35,
+        };
+        effect.set_text("MERRY XMAS");
+        effect
+    }
+
+    /// Replaces the message being shown, for use by anything other than the TUI
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().take(MAX_TEXT_LENGTH).collect();
+        self.columns = font::rasterise(&self.text);
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn cell_size(&self) -> f32 {
+        self.font_size / GLYPH_HEIGHT as f32
+    }
+
+    fn is_lit(&self, pixel: &Pixel, columns_around: f32) -> Option<bool> {
+        let top = self.height + self.font_size / 2.;
+        let row = ((top - pixel.position.y) / self.cell_size()).floor();
+        if row < 0. || row >= GLYPH_HEIGHT as f32 {
+            return None;
+        }
+
+        let around =
+            f32::atan2(pixel.position.z, pixel.position.x) / (2. * std::f32::consts::PI) + 0.5;
+        let period = self.columns.len() + MESSAGE_GAP;
+        let column = (around * columns_around + self.offset)
+            .floor()
+            .rem_euclid(period as f32) as usize;
+
+        match self.columns.get(column) {
+            Some(bits) => Some(bits & (1 << row as u32) != 0),
+            None => Some(false),
+        }
+    }
+
+    fn edit(&mut self, event: KeyEvent) {
+        let Some(buffer) = self.editing.as_mut() else {
+            return;
+        };
+
+        match event.code {
+            KeyCode::Enter => {
+                let text = buffer.clone();
+                self.set_text(&text);
+                self.editing = None;
+            }
+            KeyCode::Esc => self.editing = None,
+            KeyCode::Backspace => {
+                buffer.pop();
+            }
+            KeyCode::Char(c) if buffer.chars().count() < MAX_TEXT_LENGTH => buffer.push(c),
+            _ => {}
+        }
+    }
+}
+
+impl EffectTrait for ScrollingTextEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.ScrollingText"))
+            .set("text", self.text.clone())
+            .set("speed", format!("{:2.0}", self.speed))
+            .set("font_size", format!("{:3.0}", self.font_size))
+            .set("height", format!("{:3.0}", self.height))
+            .set("palette", self.palette.name())
+            .set("text_position", format!("{:1.2}", self.text_position))
+            .set("background", format!("{:1.2}", self.background))
+            .set(
+                "background_position",
+                format!("{:1.2}", self.background_position),
+            );
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.ScrollingText")) {
+                if let Some(text) = section.get("text") {
+                    self.set_text(text);
+                }
+
+                if let Some(speed) = section.get("speed") {
+                    self.speed = speed.parse().unwrap();
+                }
+
+                if let Some(font_size) = section.get("font_size") {
+                    self.font_size = font_size.parse().unwrap();
+                }
+
+                if let Some(height) = section.get("height") {
+                    self.height = height.parse().unwrap();
+                }
+
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(text_position) = section.get("text_position") {
+                    self.text_position = text_position.parse().unwrap();
+                }
+
+                if let Some(background) = section.get("background") {
+                    self.background = background.parse().unwrap();
+                }
+
+                if let Some(background_position) = section.get("background_position") {
+                    self.background_position = background_position.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
+        let top = self.height + self.font_size / 2.;
+        let bottom = self.height - self.font_size / 2.;
+
+        let (total, count) = pixels
+            .iter()
+            .filter(|p| p.position.y >= bottom && p.position.y <= top)
+            .fold((0., 0), |(total, count), p| {
+                (total + f32::hypot(p.position.x, p.position.z), count + 1)
+            });
+        if count > 0 && total > 0. {
+            self.band_radius = total / count as f32;
+        }
+
+        let period = (self.columns.len() + MESSAGE_GAP) as f32;
+        self.offset = (self.offset + self.speed * delta).rem_euclid(period);
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        // A whole number of columns around keeps the text from tearing at the seam
+        let columns_around = (2. * std::f32::consts::PI * self.band_radius / self.cell_size())
+            .round()
+            .max(1.);
+
+        let foreground = self.palette.sample(self.text_position);
+        let background = self.palette.sample(self.background_position);
+        let background = Colour::new(background.h, background.s, background.v * self.background);
+
+        for pixel in pixels.iter_mut() {
+            pixel.colour = match self.is_lit(pixel, columns_around) {
+                Some(true) => foreground,
+                Some(false) => background,
+                None => BLACK,
+            };
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        if self.editing.is_some() {
+            self.edit(event);
+            return;
+        }
+
+        match event.code {
+            KeyCode::Enter => self.editing = Some(self.text.clone()),
+
+            KeyCode::Char('J') => self.speed = (self.speed - 5.).clamp(-100., 100.),
+            KeyCode::Char('j') => self.speed = (self.speed - 1.).clamp(-100., 100.),
+            KeyCode::Char('K') => self.speed = (self.speed + 5.).clamp(-100., 100.),
+            KeyCode::Char('k') => self.speed = (self.speed + 1.).clamp(-100., 100.),
+
+            KeyCode::Up => self.font_size = (self.font_size + 7.).clamp(14., HEIGHT),
+            KeyCode::Down => self.font_size = (self.font_size - 7.).clamp(14., HEIGHT),
+
+            KeyCode::Char('a') => self.height = (self.height - 5.).clamp(MIN_Y, MAX_Y),
+            KeyCode::Char('s') => self.height = (self.height + 5.).clamp(MIN_Y, MAX_Y),
+
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+
+            KeyCode::Char('n') => self.text_position = (self.text_position - 0.05).rem_euclid(1.),
+            KeyCode::Char('m') => self.text_position = (self.text_position + 0.05).rem_euclid(1.),
+
+            KeyCode::Char('z') => self.background = (self.background - 0.05).clamp(0., 1.),
+            KeyCode::Char('x') => self.background = (self.background + 0.05).clamp(0., 1.),
+
+            KeyCode::Char('f') => {
+                self.background_position = (self.background_position - 0.05).rem_euclid(1.)
+            }
+            KeyCode::Char('g') => {
+                self.background_position = (self.background_position + 0.05).rem_euclid(1.)
+            }
+            _ => {}
+        }
+    }
+
+    fn captures_input(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let text_line = match &self.editing {
+            Some(buffer) => Line::from(vec![
+                Span::styled("Text: ", Style::default().fg(Color::White)),
+                Span::styled(format!("{}_", buffer), Style::default().fg(Color::Yellow)),
+                Span::styled(" (Enter/Esc)", Style::default().fg(Color::Green)),
+            ]),
+            None => Line::from(vec![
+                Span::styled("Enter ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Text: {}", self.text),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        };
+
+        let block_text = Paragraph::new(vec![
+            text_line,
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Speed: {:3.0}", self.speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("Down ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Font Size: {:3.0}", self.font_size),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" Up", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("a ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Height: {:3.0}", self.height),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" s", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Text Colour: {:1.2}", self.text_position),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("z ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Background: {:1.2}", self.background),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" x", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("f ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Background Colour: {:1.2}", self.background_position),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" g", Style::default().fg(Color::Green)),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
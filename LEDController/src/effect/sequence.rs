@@ -0,0 +1,313 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use std::sync::Arc;
+
+use crate::audio;
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::effect::file_playback::{self, Loader};
+use crate::fseq::Sequence;
+use crate::pixel::Pixel;
+
+const EXTENSION: &str = "fseq";
+/// Channels per pixel, xLights sends RGB in the order of the model's string type
+const CHANNELS: usize = 3;
+
+/// Plays an xLights FSEQ sequence, with a range of its channels mapped onto the pixels
+#[derive(Clone)]
+pub struct SequenceEffect {
+    sequence: Option<Arc<Sequence>>,
+    error: Option<String>,
+    loading: Option<Loader<Sequence>>,
+    /// Set when the settings no longer match the loaded sequence
+    stale: bool,
+    /// Pixel count the sequence was loaded for, as only those channels are kept
+    loaded_pixels: usize,
+    path: String,
+    /// First channel of the tree in the sequence, counting from 1 as xLights does
+    start_channel: usize,
+    time: f32,
+    looping: bool,
+    /// Follows the position of the WAV file being played as audio input, when there is one
+    sync_audio: bool,
+}
+
+impl SequenceEffect {
+    pub fn default() -> SequenceEffect {
+        SequenceEffect {
+            sequence: None,
+            error: None,
+            loading: None,
+            stale: true,
+            loaded_pixels: 0,
+            path: String::from("sequence.fseq"),
+            start_channel: 1,
+            time: 0.,
+            looping: true,
+            sync_audio: true,
+        }
+    }
+
+    /// Starts loading the channels for `pixel_count` pixels in the background
+    fn load_sequence(&mut self, pixel_count: usize) {
+        let path = self.path.clone();
+        let first_channel = self.start_channel.saturating_sub(1);
+        self.loading = Some(Loader::start(move || {
+            Sequence::load(&path, first_channel, pixel_count * CHANNELS)
+        }));
+        self.loaded_pixels = pixel_count;
+        self.stale = false;
+    }
+
+    /// Loads the sequence again on the next update, the current one keeps
+    /// playing until the new one is ready
+    fn reload(&mut self) {
+        self.stale = true;
+    }
+
+    fn change_start_channel(&mut self, offset: i64) {
+        self.start_channel = (self.start_channel as i64 + offset).max(1) as usize;
+        self.reload();
+    }
+
+    /// Moves on to the next sequence in the working directory, in name order
+    fn next_file(&mut self) {
+        if let Some(path) = file_playback::next_file(&self.path, EXTENSION) {
+            self.path = path;
+            self.reload();
+        }
+    }
+
+    /// Seconds into the sequence, taken from the audio when synced to it
+    fn playback_time(&self, duration: f32) -> f32 {
+        if self.sync_audio {
+            if let Some(position) = audio::features().position {
+                return position as f32;
+            }
+        }
+
+        if self.looping && duration > 0. {
+            self.time.rem_euclid(duration)
+        } else {
+            self.time
+        }
+    }
+}
+
+impl EffectTrait for SequenceEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Sequence"))
+            .set("path", self.path.clone())
+            .set("start_channel", format!("{}", self.start_channel))
+            .set("looping", format!("{}", self.looping))
+            .set("sync_audio", format!("{}", self.sync_audio));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Sequence")) {
+                if let Some(path) = section.get("path") {
+                    self.path = path.to_string();
+                }
+
+                if let Some(start_channel) = section.get("start_channel") {
+                    self.start_channel = start_channel.parse().unwrap();
+                }
+
+                if let Some(looping) = section.get("looping") {
+                    self.looping = looping.parse().unwrap();
+                }
+
+                if let Some(sync_audio) = section.get("sync_audio") {
+                    self.sync_audio = sync_audio.parse().unwrap();
+                }
+            }
+        }
+
+        self.reload();
+    }
+
+    fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
+        if let Some(result) = self.loading.as_ref().and_then(Loader::finished) {
+            match result {
+                Ok(sequence) => {
+                    self.sequence = Some(Arc::new(sequence));
+                    self.error = None;
+                }
+                Err(err) => {
+                    self.sequence = None;
+                    self.error = Some(err);
+                }
+            }
+            self.loading = None;
+            self.time = 0.;
+        }
+
+        // One load at a time, anything changed meanwhile is loaded once it's done
+        if self.loading.is_none() && (self.stale || self.loaded_pixels != pixels.len()) {
+            self.load_sequence(pixels.len());
+        }
+
+        self.time += delta;
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        let Some(sequence) = &self.sequence else {
+            for pixel in pixels.iter_mut() {
+                pixel.colour = BLACK;
+            }
+            return;
+        };
+
+        let index = sequence.frame_index(self.playback_time(sequence.duration()));
+        file_playback::show_rgb(sequence.frame(index), pixels);
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('c') => self.next_file(),
+            KeyCode::Char('l') => self.reload(),
+            KeyCode::Char('b') => self.looping = !self.looping,
+            KeyCode::Char('v') => self.sync_audio = !self.sync_audio,
+            KeyCode::Char('s') => self.time = 0.,
+
+            KeyCode::Char('N') => self.change_start_channel(-(100 * CHANNELS as i64)),
+            KeyCode::Char('n') => self.change_start_channel(-(CHANNELS as i64)),
+            KeyCode::Char('M') => self.change_start_channel(100 * CHANNELS as i64),
+            KeyCode::Char('m') => self.change_start_channel(CHANNELS as i64),
+            _ => {}
+        }
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some() || self.stale
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let status = match (&self.sequence, &self.error) {
+            _ if self.loading.is_some() => Span::styled(
+                format!("Loading {}", self.path),
+                Style::default().fg(Color::Yellow),
+            ),
+            (Some(sequence), _) => Span::styled(
+                format!(
+                    "{} ({} frames at {}ms, {} channels)",
+                    self.path,
+                    sequence.frame_count(),
+                    sequence.step_ms,
+                    sequence.file_channels
+                ),
+                Style::default().fg(Color::Green),
+            ),
+            (None, Some(err)) => Span::styled(err.clone(), Style::default().fg(Color::Red)),
+            (None, None) => Span::styled(self.path.clone(), Style::default().fg(Color::Red)),
+        };
+
+        let position = match &self.sequence {
+            Some(sequence) => {
+                let time = self.playback_time(sequence.duration());
+                format!(
+                    "{:.2}s / {:.2}s, frame {}",
+                    time,
+                    sequence.duration(),
+                    sequence.frame_index(time)
+                )
+            }
+            None => String::from("-"),
+        };
+
+        let sync = match (self.sync_audio, audio::features().position) {
+            (false, _) => "Off",
+            (true, Some(_)) => "Following Audio",
+            (true, None) => "On, No WAV Playing",
+        };
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("l ", Style::default().fg(Color::Green)),
+                status,
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Green)),
+                Span::styled("Next Sequence", Style::default().fg(Color::White)),
+            ]),
+            Line::from(vec![
+                Span::styled("N ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!(
+                        "Start Channel: {} (to {})",
+                        self.start_channel,
+                        self.start_channel + self.loaded_pixels * CHANNELS - 1
+                    ),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" M", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Looping: {}", if self.looping { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("v ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Audio Sync: {}", sync),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("s ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Restart ({})", position),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
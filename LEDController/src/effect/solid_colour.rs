@@ -10,6 +10,7 @@ use ratatui::{
 use ini::Ini;
 
 use crate::colour::*;
+use crate::config;
 use crate::effect::constants::*;
 use crate::effect::effect_trait::EffectTrait;
 use crate::pixel::Pixel;
@@ -26,6 +27,10 @@ impl SolidColourEffect {
 }
 
 impl EffectTrait for SolidColourEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
     fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
         self
     }
@@ -40,7 +45,7 @@ impl EffectTrait for SolidColourEffect {
             .with_section(Some("Effect.SolidColour"))
             .set("colour", self.colour.to_string());
 
-        config.write_to_file(CONFIG_NAME).unwrap();
+        config::save(&config);
     }
 
     fn read_settings(&mut self) {
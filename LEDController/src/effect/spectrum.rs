@@ -0,0 +1,239 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use crate::audio::{self, NUM_BANDS};
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+
+/// Splits the layout into horizontal slices by height, one per frequency band,
+/// with the bass at the bottom and the treble at the top
+#[derive(Clone)]
+pub struct SpectrumEffect {
+    levels: [f32; NUM_BANDS],
+    gain: f32,
+    smoothing: f32,
+    hue_start: f32,
+    hue_range: f32,
+    bars: bool,
+    palette: Palette,
+}
+
+impl SpectrumEffect {
+    pub fn default() -> SpectrumEffect {
+        SpectrumEffect {
+            levels: [0.; NUM_BANDS],
+            gain: 1.,
+            smoothing: 0.6,
+            hue_start: 0.,
+            hue_range: 240.,
+            bars: false,
+            palette: Palette::rainbow(),
+        }
+    }
+}
+
+impl EffectTrait for SpectrumEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        config
+            .with_section(Some("Effect.Spectrum"))
+            .set("palette", self.palette.name())
+            .set("gain", format!("{:1.2}", self.gain))
+            .set("smoothing", format!("{:1.2}", self.smoothing))
+            .set("hue_start", format!("{:3.0}", self.hue_start))
+            .set("hue_range", format!("{:3.0}", self.hue_range))
+            .set("bars", format!("{}", self.bars));
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some("Effect.Spectrum")) {
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+
+                if let Some(gain) = section.get("gain") {
+                    self.gain = gain.parse().unwrap();
+                }
+
+                if let Some(smoothing) = section.get("smoothing") {
+                    self.smoothing = smoothing.parse().unwrap();
+                }
+
+                if let Some(hue_start) = section.get("hue_start") {
+                    self.hue_start = hue_start.parse().unwrap();
+                }
+
+                if let Some(hue_range) = section.get("hue_range") {
+                    self.hue_range = hue_range.parse().unwrap();
+                }
+
+                if let Some(bars) = section.get("bars") {
+                    self.bars = bars.parse().unwrap();
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, _delta: f32, _pixels: &Vec<Pixel>) {
+        let features = audio::features();
+
+        for (level, band) in self.levels.iter_mut().zip(features.bands.iter()) {
+            let target = (band * self.gain).clamp(0., 1.);
+            // Rise instantly but fall slowly so the display doesn't flicker
+            *level = if target > *level {
+                target
+            } else {
+                *level * self.smoothing + target * (1. - self.smoothing)
+            };
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        for pixel in pixels.iter_mut() {
+            let t = ((pixel.position.y - MIN_Y) / HEIGHT).clamp(0., 0.9999);
+            let band_position = t * NUM_BANDS as f32;
+            let band = band_position as usize;
+            let level = self.levels[band];
+
+            // In bar mode each slice fills upwards like a level meter
+            let value = if self.bars {
+                if band_position.fract() < level {
+                    1.
+                } else {
+                    0.
+                }
+            } else {
+                level
+            };
+
+            let colour = self
+                .palette
+                .sample((self.hue_start + self.hue_range * t) / 360.);
+            pixel.colour = Colour::new(colour.h, colour.s, colour.v * value);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('K') => self.gain = (self.gain + 0.5).clamp(0.1, 10.),
+            KeyCode::Char('k') => self.gain = (self.gain + 0.1).clamp(0.1, 10.),
+            KeyCode::Char('J') => self.gain = (self.gain - 0.5).clamp(0.1, 10.),
+            KeyCode::Char('j') => self.gain = (self.gain - 0.1).clamp(0.1, 10.),
+
+            KeyCode::Char('n') => self.smoothing = (self.smoothing - 0.05).clamp(0., 0.95),
+            KeyCode::Char('m') => self.smoothing = (self.smoothing + 0.05).clamp(0., 0.95),
+
+            KeyCode::Char('h') => self.hue_start = (self.hue_start + 10.) % 360.,
+            KeyCode::Char('H') => self.hue_start = (self.hue_start + 350.) % 360.,
+
+            KeyCode::Up => self.hue_range = (self.hue_range + 10.).clamp(-360., 360.),
+            KeyCode::Down => self.hue_range = (self.hue_range - 10.).clamp(-360., 360.),
+
+            KeyCode::Char('b') => self.bars = !self.bars,
+            KeyCode::Char('p') => self.palette = self.palette.next(),
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let audio_status = if audio::features().active {
+            Span::styled("Audio: Active", Style::default().fg(Color::Green))
+        } else {
+            Span::styled("Audio: No Input", Style::default().fg(Color::Red))
+        };
+
+        let block_text = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(audio_status),
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Gain: {:1.1}", self.gain),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Smoothing: {:1.2}", self.smoothing),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("H ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Hue Start: {:3.0}", self.hue_start),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" h", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Hue Range: {:4.0}", self.hue_range),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Bars: {}", if self.bars { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+        ])
+        .centered()
+        .block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
@@ -0,0 +1,401 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use ini::Ini;
+
+use crate::colour::*;
+use crate::config;
+use crate::effect::constants::*;
+use crate::effect::effect_trait::EffectTrait;
+use crate::palette::Palette;
+use crate::pixel::Pixel;
+use crate::tempo;
+
+/// Colours the wipe steps through before coming back round to the start of the palette
+const WIPE_STEPS: u32 = 6;
+
+/// Effects that animate along the wiring order of the strand rather than the
+/// calibrated positions, so they work even without an Output.pixels file
+#[derive(Copy, Clone, PartialEq)]
+pub enum StrandMode {
+    Comet,
+    TheatreChase,
+    LarsonScanner,
+    ColourWipe,
+}
+
+impl StrandMode {
+    fn section(self) -> &'static str {
+        match self {
+            StrandMode::Comet => "Effect.Comet",
+            StrandMode::TheatreChase => "Effect.TheatreChase",
+            StrandMode::LarsonScanner => "Effect.LarsonScanner",
+            StrandMode::ColourWipe => "Effect.ColourWipe",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StrandChaseEffect {
+    mode: StrandMode,
+    position: f32,
+    num_pixels: usize,
+    cycles: u32,
+    speed: f32,
+    length: usize,
+    spacing: usize,
+    reverse: bool,
+    tempo_sync: bool,
+    beats_per_cycle: f32,
+    colour: Colour,
+    /// The colour wipe steps through this rather than a single colour
+    palette: Palette,
+}
+
+impl StrandChaseEffect {
+    fn new(mode: StrandMode, length: usize, colour: Colour) -> StrandChaseEffect {
+        StrandChaseEffect {
+            mode,
+            position: 0.,
+            num_pixels: 0,
+            cycles: 0,
+            speed: 60.,
+            length,
+            spacing: 3,
+            reverse: false,
+            tempo_sync: false,
+            beats_per_cycle: 4.,
+            colour,
+            palette: Palette::rainbow(),
+        }
+    }
+
+    pub fn comet() -> StrandChaseEffect {
+        StrandChaseEffect::new(StrandMode::Comet, 20, CYAN)
+    }
+
+    pub fn theatre_chase() -> StrandChaseEffect {
+        StrandChaseEffect::new(StrandMode::TheatreChase, 1, YELLOW)
+    }
+
+    pub fn larson_scanner() -> StrandChaseEffect {
+        StrandChaseEffect::new(StrandMode::LarsonScanner, 8, RED)
+    }
+
+    pub fn colour_wipe() -> StrandChaseEffect {
+        StrandChaseEffect::new(StrandMode::ColourWipe, 1, GREEN)
+    }
+
+    /// Length of one full cycle of the animation, in pixels travelled
+    fn cycle_length(&self) -> f32 {
+        let n = self.num_pixels as f32;
+        match self.mode {
+            StrandMode::Comet => n + self.length as f32,
+            StrandMode::TheatreChase => (self.length + self.spacing) as f32,
+            StrandMode::LarsonScanner => 2. * (n - 1.).max(1.),
+            StrandMode::ColourWipe => n,
+        }
+    }
+
+    /// Maps a strand index onto the direction of travel
+    fn directed(&self, index: usize) -> usize {
+        if self.reverse {
+            self.num_pixels - 1 - index
+        } else {
+            index
+        }
+    }
+
+    /// Brightness of a pixel `behind` pixels behind the head of a tail
+    fn fade(&self, behind: f32) -> f32 {
+        if behind < 0. || behind >= self.length as f32 {
+            0.
+        } else {
+            1. - behind / self.length as f32
+        }
+    }
+
+    fn value_at(&self, index: usize) -> (f32, Colour) {
+        match self.mode {
+            StrandMode::Comet => (self.fade(self.position - index as f32), self.colour),
+            StrandMode::TheatreChase => {
+                let offset = (index as f32 - self.position).rem_euclid(self.cycle_length());
+                let lit = offset < self.length as f32;
+                (if lit { 1. } else { 0. }, self.colour)
+            }
+            StrandMode::LarsonScanner => {
+                let last = (self.num_pixels as f32 - 1.).max(1.);
+                // The tail trails behind the head whichever way it is travelling
+                let behind = if self.position <= last {
+                    self.position - index as f32
+                } else {
+                    index as f32 - (2. * last - self.position)
+                };
+                (self.fade(behind), self.colour)
+            }
+            StrandMode::ColourWipe => {
+                let wipe_colour = |cycle: u32| {
+                    self.palette
+                        .sample((cycle % WIPE_STEPS) as f32 / WIPE_STEPS as f32)
+                };
+                let previous = wipe_colour(self.cycles + WIPE_STEPS - 1);
+                let current = wipe_colour(self.cycles);
+                if (index as f32) < self.position {
+                    (1., current)
+                } else if self.cycles > 0 {
+                    (1., previous)
+                } else {
+                    (0., current)
+                }
+            }
+        }
+    }
+}
+
+impl EffectTrait for StrandChaseEffect {
+    fn as_trait(&self) -> &dyn EffectTrait {
+        self
+    }
+
+    fn as_trait_mut(&mut self) -> &mut dyn EffectTrait {
+        self
+    }
+
+    fn save_settings(&self) {
+        let mut config: Ini = Ini::new();
+        if let Ok(x) = Ini::load_from_file(CONFIG_NAME) {
+            config = x;
+        }
+
+        let mut section = config.with_section(Some(self.mode.section()));
+        section
+            .set("speed", format!("{:3.0}", self.speed))
+            .set("length", format!("{}", self.length))
+            .set("spacing", format!("{}", self.spacing))
+            .set("reverse", format!("{}", self.reverse))
+            .set("tempo_sync", format!("{}", self.tempo_sync))
+            .set("beats_per_cycle", format!("{}", self.beats_per_cycle));
+
+        if self.mode == StrandMode::ColourWipe {
+            section.set("palette", self.palette.name());
+        } else {
+            section.set("colour", self.colour.to_string());
+        }
+
+        config::save(&config);
+    }
+
+    fn read_settings(&mut self) {
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some(self.mode.section())) {
+                if let Some(speed) = section.get("speed") {
+                    self.speed = speed.parse().unwrap();
+                }
+
+                if let Some(length) = section.get("length") {
+                    self.length = length.parse().unwrap();
+                }
+
+                if let Some(spacing) = section.get("spacing") {
+                    self.spacing = spacing.parse().unwrap();
+                }
+
+                if let Some(reverse) = section.get("reverse") {
+                    self.reverse = reverse.parse().unwrap();
+                }
+
+                if let Some(tempo_sync) = section.get("tempo_sync") {
+                    self.tempo_sync = tempo_sync.parse().unwrap();
+                }
+
+                if let Some(beats_per_cycle) = section.get("beats_per_cycle") {
+                    self.beats_per_cycle = beats_per_cycle.parse().unwrap();
+                }
+
+                if let Some(colour) = section.get("colour") {
+                    let values: Vec<f32> = colour.split(",").map(|v| v.parse().unwrap()).collect();
+                    self.colour.h = values[0];
+                    self.colour.s = values[1];
+                    self.colour.v = values[2];
+                }
+
+                if let Some(palette) = section.get("palette") {
+                    self.palette = Palette::by_name(palette);
+                }
+            }
+        }
+
+        // Keep to the ranges the keys allow, as a cycle of no length never advances
+        self.speed = self.speed.clamp(1., 1000.);
+        self.length = self.length.clamp(1, 100);
+        self.spacing = self.spacing.clamp(1, 50);
+        self.beats_per_cycle = self.beats_per_cycle.clamp(0.25, 64.);
+    }
+
+    fn update(&mut self, delta: f32, pixels: &Vec<Pixel>) {
+        self.num_pixels = pixels.len();
+        if self.num_pixels == 0 {
+            return;
+        }
+
+        let cycle_length = self.cycle_length();
+        let speed = if self.tempo_sync {
+            tempo::speed_for(cycle_length, self.beats_per_cycle)
+        } else {
+            self.speed
+        };
+
+        self.position += speed * delta;
+        if self.position >= cycle_length {
+            self.position %= cycle_length;
+            self.cycles += 1;
+        }
+    }
+
+    fn render(&self, pixels: &mut Vec<Pixel>) {
+        if self.num_pixels != pixels.len() {
+            return;
+        }
+
+        for index in 0..pixels.len() {
+            let (value, colour) = self.value_at(index);
+            pixels[self.directed(index)].colour = Colour::new(colour.h, colour.s, colour.v * value);
+        }
+    }
+
+    fn handle_input(&mut self, event: KeyEvent) {
+        match event.code {
+            KeyCode::Char('K') => self.speed = (self.speed + 10.).clamp(1., 1000.),
+            KeyCode::Char('k') => self.speed = (self.speed + 5.).clamp(1., 1000.),
+            KeyCode::Char('J') => self.speed = (self.speed - 10.).clamp(1., 1000.),
+            KeyCode::Char('j') => self.speed = (self.speed - 5.).clamp(1., 1000.),
+
+            KeyCode::Up => self.length = (self.length + 1).clamp(1, 100),
+            KeyCode::Down => self.length = (self.length.saturating_sub(1)).clamp(1, 100),
+
+            KeyCode::Char('n') => self.spacing = (self.spacing.saturating_sub(1)).clamp(1, 50),
+            KeyCode::Char('m') => self.spacing = (self.spacing + 1).clamp(1, 50),
+
+            KeyCode::Char('d') => self.reverse = !self.reverse,
+
+            KeyCode::Char('b') => self.tempo_sync = !self.tempo_sync,
+            KeyCode::Char('c') => {
+                self.beats_per_cycle = (self.beats_per_cycle / 2.).clamp(0.25, 64.)
+            }
+            KeyCode::Char('v') => {
+                self.beats_per_cycle = (self.beats_per_cycle * 2.).clamp(0.25, 64.)
+            }
+
+            KeyCode::Char('h') if self.mode != StrandMode::ColourWipe => {
+                self.colour.h = (self.colour.h + 10.) % 360.
+            }
+            KeyCode::Char('H') if self.mode != StrandMode::ColourWipe => {
+                self.colour.h = (self.colour.h + 350.) % 360.
+            }
+
+            KeyCode::Char('p') if self.mode == StrandMode::ColourWipe => {
+                self.palette = self.palette.next()
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, layout: Rect) {
+        let blocks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(33),
+                Constraint::Min(1),
+                Constraint::Percentage(33),
+            ])
+            .split(layout);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default());
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled("J ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Speed: {:3.0}", self.speed),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" K", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("b ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Tempo Sync: {}", if self.tempo_sync { "On" } else { "Off" }),
+                    Style::default().fg(Color::White),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("c ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Beats per Cycle: {}", self.beats_per_cycle),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" v", Style::default().fg(Color::Green)),
+            ]),
+            Line::from(vec![
+                Span::styled("<down> ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Length: {:3}", self.length),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" <up>", Style::default().fg(Color::Green)),
+            ]),
+        ];
+
+        if self.mode == StrandMode::TheatreChase {
+            lines.push(Line::from(vec![
+                Span::styled("n ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Spacing: {:2}", self.spacing),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" m", Style::default().fg(Color::Green)),
+            ]));
+        }
+
+        lines.push(Line::from(vec![
+            Span::styled("d ", Style::default().fg(Color::Green)),
+            Span::styled(
+                format!(
+                    "Direction: {}",
+                    if self.reverse { "Reverse" } else { "Forward" }
+                ),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+        if self.mode == StrandMode::ColourWipe {
+            lines.push(Line::from(vec![
+                Span::styled("p ", Style::default().fg(Color::Green)),
+                Span::styled(
+                    format!("Palette: {}", self.palette.name()),
+                    Style::default().fg(Color::White),
+                ),
+            ]));
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled("H ", Style::default().fg(Color::Red)),
+                Span::styled(
+                    format!("Hue: {:3.0}", self.colour.h),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(" h", Style::default().fg(Color::Green)),
+            ]));
+        }
+
+        let block_text = Paragraph::new(lines).centered().block(block);
+
+        frame.render_widget(block_text, blocks[1]);
+    }
+}
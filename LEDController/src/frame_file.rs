@@ -0,0 +1,309 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+
+use crate::bytes::{read_u16, read_u32};
+
+/// Marks the start of every frame file
+const MAGIC: &[u8; 4] = b"LEDF";
+const HEADER_SIZE: usize = 14;
+/// Frames at a fixed rate, stored whole
+const RENDERED_VERSION: u8 = 1;
+/// Timestamped frames, each stored as the changes from the one before
+const RECORDED_VERSION: u8 = 2;
+/// As recorded frames, with the bytes that were sent stored after the colours
+const RECORDED_SENT_VERSION: u8 = 3;
+/// Frames are always stored as RGB, whatever the output sends
+pub const CHANNELS: usize = 3;
+/// Unchanged bytes shorter than this are stored rather than starting a new run,
+/// as a run costs 4 bytes of its own
+const MIN_GAP: usize = 4;
+
+/// Every frame file starts with a 14 byte header: `LEDF`, a version byte, a byte
+/// for the number of channels, then the pixel count as a little endian u32 and the
+/// frame rate as a little endian f32
+fn write_header(
+    file: &mut impl Write,
+    version: u8,
+    pixel_count: usize,
+    fps: f32,
+) -> io::Result<()> {
+    file.write_all(MAGIC)?;
+    file.write_all(&[version, CHANNELS as u8])?;
+    file.write_all(&(pixel_count as u32).to_le_bytes())?;
+    file.write_all(&fps.to_le_bytes())
+}
+
+fn wrong_size(data: &[u8], frame_size: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Frame is {} bytes but should be {}", data.len(), frame_size),
+    )
+}
+
+/// Writes frames rendered at a fixed rate to a file, each frame following the
+/// header as pixel count * channels bytes
+pub struct FrameWriter {
+    file: BufWriter<File>,
+    frame_size: usize,
+}
+
+impl FrameWriter {
+    pub fn create(path: &str, pixel_count: usize, fps: f32) -> io::Result<FrameWriter> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_header(&mut file, RENDERED_VERSION, pixel_count, fps)?;
+
+        Ok(FrameWriter {
+            file,
+            frame_size: pixel_count * CHANNELS,
+        })
+    }
+
+    pub fn write_frame(&mut self, data: &[u8]) -> io::Result<()> {
+        if data.len() != self.frame_size {
+            return Err(wrong_size(data, self.frame_size));
+        }
+
+        self.file.write_all(data)
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Records frames as they are sent along with when they were sent, keeping both
+/// the RGB colours, for playing back, and the bytes that went to the output after
+/// correction and brightness.
+///
+/// The header is followed by a byte for the number of channels sent per pixel.
+/// Each frame is then the milliseconds since recording started, followed by the
+/// colours and then the sent bytes. Both are stored as the length of what follows,
+/// a little endian u32, then runs of changed bytes. Each run is the number of
+/// unchanged bytes to skip and the number of bytes that follow, both little endian
+/// u16s. Anything not covered by a run is the same as in the previous frame, which
+/// starts out black, so still scenes take up almost no space
+pub struct FrameRecorder {
+    file: BufWriter<File>,
+    path: String,
+    start: Instant,
+    previous: Vec<u8>,
+    previous_sent: Vec<u8>,
+}
+
+impl FrameRecorder {
+    pub fn create(
+        path: &str,
+        pixel_count: usize,
+        sent_channels: usize,
+        fps: f32,
+    ) -> io::Result<FrameRecorder> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_header(&mut file, RECORDED_SENT_VERSION, pixel_count, fps)?;
+        file.write_all(&[sent_channels as u8])?;
+
+        Ok(FrameRecorder {
+            file,
+            path: path.to_string(),
+            start: Instant::now(),
+            previous: vec![0; pixel_count * CHANNELS],
+            previous_sent: vec![0; pixel_count * sent_channels],
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+
+    /// Adds a frame of RGB `colours` and the `sent` bytes they became
+    pub fn record(&mut self, colours: &[u8], sent: &[u8]) -> io::Result<()> {
+        if colours.len() != self.previous.len() {
+            return Err(wrong_size(colours, self.previous.len()));
+        }
+        if sent.len() != self.previous_sent.len() {
+            return Err(wrong_size(sent, self.previous_sent.len()));
+        }
+
+        let millis = self.start.elapsed().as_millis() as u32;
+        self.file.write_all(&millis.to_le_bytes())?;
+
+        for (previous, data) in [
+            (&mut self.previous, colours),
+            (&mut self.previous_sent, sent),
+        ] {
+            let mut runs = Vec::new();
+            encode_changes(previous, data, &mut runs);
+
+            self.file.write_all(&(runs.len() as u32).to_le_bytes())?;
+            self.file.write_all(&runs)?;
+            previous.copy_from_slice(data);
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn encode_changes(previous: &[u8], data: &[u8], out: &mut Vec<u8>) {
+    let max_run = u16::MAX as usize;
+    let changed = |i: usize| data[i] != previous[i];
+
+    let mut pos = 0;
+    while let Some(start) = (pos..data.len()).find(|i| changed(*i)) {
+        // Runs can only skip so far, so long gaps are crossed with empty runs
+        if start - pos > max_run {
+            out.extend((max_run as u16).to_le_bytes());
+            out.extend(0u16.to_le_bytes());
+            pos += max_run;
+            continue;
+        }
+
+        let limit = data.len().min(start + max_run);
+        let mut end = start + 1;
+        while end < limit {
+            if changed(end) {
+                end += 1;
+            } else if let Some(next) = (end..limit.min(end + MIN_GAP)).find(|i| changed(*i)) {
+                end = next + 1;
+            } else {
+                break;
+            }
+        }
+
+        out.extend(((start - pos) as u16).to_le_bytes());
+        out.extend(((end - start) as u16).to_le_bytes());
+        out.extend(&data[start..end]);
+        pos = end;
+    }
+}
+
+/// Applies the runs of changed bytes written by `encode_changes` to `frame`, giving
+/// None if they don't fit
+fn apply_changes(runs: &[u8], frame: &mut [u8]) -> Option<()> {
+    let mut run = 0;
+    let mut pos = 0;
+    while run < runs.len() {
+        let skip = read_u16(runs, run)?;
+        let count = read_u16(runs, run + 2)?;
+        let bytes = runs.get(run + 4..run + 4 + count)?;
+        pos += skip;
+        frame.get_mut(pos..pos + count)?.copy_from_slice(bytes);
+        pos += count;
+        run += 4 + count;
+    }
+    Some(())
+}
+
+/// A rendered or recorded frame file loaded into memory
+pub struct FrameFile {
+    pub pixel_count: usize,
+    pub fps: f32,
+    /// Time in seconds at which each frame starts
+    times: Vec<f32>,
+    frames: Vec<Vec<u8>>,
+}
+
+impl FrameFile {
+    pub fn load(path: &str) -> Result<FrameFile, String> {
+        let data = fs::read(path).map_err(|err| err.to_string())?;
+        let corrupt = || format!("{} is not a valid frame file", path);
+
+        if data.len() < HEADER_SIZE || &data[0..4] != MAGIC {
+            return Err(corrupt());
+        }
+
+        let version = data[4];
+        let channels = data[5] as usize;
+        let pixel_count = read_u32(&data, 6).ok_or_else(corrupt)?;
+        let fps = f32::from_le_bytes(data[10..14].try_into().unwrap());
+
+        if channels != CHANNELS || fps <= 0. {
+            return Err(corrupt());
+        }
+
+        let frame_size = pixel_count * CHANNELS;
+        let body = &data[HEADER_SIZE..];
+
+        let mut file = FrameFile {
+            pixel_count,
+            fps,
+            times: Vec::new(),
+            frames: Vec::new(),
+        };
+
+        match version {
+            RENDERED_VERSION => {
+                for (i, frame) in body.chunks_exact(frame_size.max(1)).enumerate() {
+                    file.times.push(i as f32 / fps);
+                    file.frames.push(frame.to_vec());
+                }
+            }
+            RECORDED_VERSION | RECORDED_SENT_VERSION => {
+                // Only the colours are played back, so the sent bytes are skipped
+                let has_sent = version == RECORDED_SENT_VERSION;
+                let body = if has_sent {
+                    body.get(1..).ok_or_else(corrupt)?
+                } else {
+                    body
+                };
+
+                let mut previous = vec![0; frame_size];
+                let mut at = 0;
+
+                while at < body.len() {
+                    let millis = read_u32(body, at).ok_or_else(corrupt)?;
+                    let length = read_u32(body, at + 4).ok_or_else(corrupt)?;
+                    let runs = body.get(at + 8..at + 8 + length).ok_or_else(corrupt)?;
+                    at += 8 + length;
+
+                    if has_sent {
+                        let sent_length = read_u32(body, at).ok_or_else(corrupt)?;
+                        body.get(at + 4..at + 4 + sent_length).ok_or_else(corrupt)?;
+                        at += 4 + sent_length;
+                    }
+
+                    let mut frame = previous.clone();
+                    apply_changes(runs, &mut frame).ok_or_else(corrupt)?;
+
+                    file.times.push(millis as f32 / 1000.);
+                    file.frames.push(frame.clone());
+                    previous = frame;
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "{} is version {}, which isn't supported",
+                    path, version
+                ))
+            }
+        }
+
+        if file.frames.is_empty() {
+            return Err(format!("No frames found in {}", path));
+        }
+
+        Ok(file)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Length in seconds, with the last frame shown for one frame period
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.) + 1. / self.fps
+    }
+
+    /// The frame showing at `time` seconds in
+    pub fn frame_at(&self, time: f32) -> &[u8] {
+        let index = self.times.partition_point(|t| *t <= time).max(1) - 1;
+        &self.frames[index]
+    }
+}
@@ -0,0 +1,187 @@
+use ruzstd::decoding::StreamingDecoder;
+
+use std::fs;
+use std::io::Read;
+
+use crate::bytes::{read_u16, read_u24, read_u32};
+
+const HEADER_SIZE: usize = 32;
+const BLOCK_SIZE: usize = 8;
+const SPARSE_RANGE_SIZE: usize = 6;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+
+/// A sequence exported from xLights as an FSEQ v2 file.
+///
+/// Only the channels mapped onto the pixels are kept, so long shows with many
+/// props don't have to fit in memory
+pub struct Sequence {
+    /// Time each frame is shown for, in milliseconds
+    pub step_ms: u32,
+    /// Channels in each frame of the file, of which `frames` only holds some
+    pub file_channels: usize,
+    frames: Vec<Vec<u8>>,
+}
+
+impl Sequence {
+    /// Loads `channel_count` channels starting at `first_channel`, counting from 0.
+    /// Channels the file doesn't have are left at 0
+    pub fn load(
+        path: &str,
+        first_channel: usize,
+        channel_count: usize,
+    ) -> Result<Sequence, String> {
+        let data = fs::read(path).map_err(|err| err.to_string())?;
+        let corrupt = || format!("{} is not a valid FSEQ file", path);
+
+        if data.len() < HEADER_SIZE || (&data[0..4] != b"PSEQ" && &data[0..4] != b"FSEQ") {
+            return Err(corrupt());
+        }
+
+        let data_offset = read_u16(&data, 4).ok_or_else(corrupt)?;
+        let major_version = data[7];
+        let frame_channels = read_u32(&data, 10).ok_or_else(corrupt)?;
+        let frame_count = read_u32(&data, 14).ok_or_else(corrupt)?;
+        let step_ms = data[18] as u32;
+        let compression = data[20] & 0x0F;
+        let block_count = ((data[20] as usize >> 4) << 8) | data[21] as usize;
+        let sparse_count = data[22] as usize;
+
+        if major_version != 2 {
+            return Err(format!(
+                "{} is FSEQ version {}, only version 2 is supported",
+                path, major_version
+            ));
+        }
+        if step_ms == 0 || frame_channels == 0 || data_offset > data.len() {
+            return Err(corrupt());
+        }
+
+        let blocks_start = HEADER_SIZE;
+        let sparse_start = blocks_start + block_count * BLOCK_SIZE;
+        if sparse_start + sparse_count * SPARSE_RANGE_SIZE > data_offset {
+            return Err(corrupt());
+        }
+
+        // Sparse files only store some ranges of channels, one after the other
+        let mut file_channels = frame_channels;
+        let mut index: Vec<Option<usize>> = vec![None; channel_count];
+        if sparse_count == 0 {
+            for (channel, position) in index.iter_mut().enumerate() {
+                let channel = first_channel + channel;
+                if channel < frame_channels {
+                    *position = Some(channel);
+                }
+            }
+        } else {
+            let mut stored = 0;
+            file_channels = 0;
+            for range in 0..sparse_count {
+                let at = sparse_start + range * SPARSE_RANGE_SIZE;
+                let start = read_u24(&data, at).ok_or_else(corrupt)?;
+                let count = read_u24(&data, at + 3).ok_or_else(corrupt)?;
+
+                for (channel, position) in index.iter_mut().enumerate() {
+                    let channel = first_channel + channel;
+                    if channel >= start && channel < start + count {
+                        *position = Some(stored + channel - start);
+                    }
+                }
+                stored += count;
+                file_channels = file_channels.max(start + count);
+            }
+
+            // Each frame holds the ranges back to back, so they have to fit in it
+            if stored > frame_channels {
+                return Err(corrupt());
+            }
+        }
+
+        // The frame count in the header can't be trusted to size the buffer, so it
+        // is capped at what the file could hold uncompressed. Compressed files can
+        // hold more, and the buffer simply grows for them
+        let channel_data = &data[data_offset..];
+        let mut sequence = Sequence {
+            step_ms,
+            file_channels,
+            frames: Vec::with_capacity(frame_count.min(channel_data.len() / frame_channels)),
+        };
+
+        let mut add_frames = |bytes: &[u8]| {
+            for frame in bytes.chunks_exact(frame_channels) {
+                if sequence.frames.len() < frame_count {
+                    sequence.frames.push(
+                        index
+                            .iter()
+                            .map(|position| position.map_or(0, |p| frame[p]))
+                            .collect(),
+                    );
+                }
+            }
+        };
+
+        match compression {
+            COMPRESSION_NONE => add_frames(channel_data),
+            COMPRESSION_ZSTD => {
+                let mut offset = 0;
+                for block in 0..block_count {
+                    let length = read_u32(&data, blocks_start + block * BLOCK_SIZE + 4)
+                        .ok_or_else(corrupt)?;
+                    let mut compressed = channel_data
+                        .get(offset..offset + length)
+                        .ok_or_else(corrupt)?;
+                    offset += length;
+
+                    // xLights leaves unused entries at the end of the index, and a
+                    // block may hold more than one zstd frame
+                    let mut bytes = Vec::new();
+                    while !compressed.is_empty() {
+                        let decompress_error = |err: &dyn std::fmt::Display| {
+                            format!("Couldn't decompress {}: {}", path, err)
+                        };
+                        let mut decoder = StreamingDecoder::new(&mut compressed)
+                            .map_err(|e| decompress_error(&e))?;
+                        decoder
+                            .read_to_end(&mut bytes)
+                            .map_err(|e| decompress_error(&e))?;
+                    }
+                    add_frames(&bytes);
+                }
+            }
+            COMPRESSION_ZLIB => {
+                return Err(format!(
+                    "{} is zlib compressed, re-save it with zstd or no compression",
+                    path
+                ))
+            }
+            _ => return Err(corrupt()),
+        }
+
+        if sequence.frames.is_empty() {
+            return Err(format!("No frames found in {}", path));
+        }
+
+        Ok(sequence)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 * self.step_ms as f32 / 1000.
+    }
+
+    /// The frame showing at `time` seconds in, holding the last frame after the end
+    pub fn frame_index(&self, time: f32) -> usize {
+        let index = (time.max(0.) * 1000. / self.step_ms as f32) as usize;
+        index.min(self.frames.len() - 1)
+    }
+
+    pub fn frame(&self, index: usize) -> &[u8] {
+        &self.frames[index]
+    }
+}
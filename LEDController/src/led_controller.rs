@@ -1,9 +1,13 @@
 use crate::colour::*;
+use crate::config;
 use crate::effect::{constants::CONFIG_NAME, effect_list::Effect};
+use crate::output::OutputSettings;
+use crate::pipeline::RenderedFrame;
 use crate::pixel::Pixel;
+use crate::power::PowerModel;
+use crate::tempo;
 use crate::vec3::Vec3;
 
-use ddp_rs::connection;
 use ini::Ini;
 
 use regex::Regex;
@@ -16,14 +20,21 @@ pub struct PixelController {
     pixels: Vec<Pixel>,
     effect: Effect,
     max_brightness: f32,
+    output: OutputSettings,
+    power: PowerModel,
+    /// Rounding error of each pixel from the last frame, used for dithering
+    dither_error: Vec<[f32; 4]>,
 }
 
 impl PixelController {
-    pub fn new(num_pixels: usize) -> PixelController {
+    pub fn new(num_pixels: usize, output: OutputSettings, power: PowerModel) -> PixelController {
         let mut controller = PixelController {
             pixels: Vec::new(),
             effect: Effect::id_to_effect(0),
             max_brightness: 0.2,
+            output,
+            power,
+            dither_error: Vec::new(),
         };
 
         controller.pixels.resize(
@@ -44,14 +55,41 @@ impl PixelController {
         self.pixels.len()
     }
 
-    pub fn get_current_effect(&self) -> Effect {
-        self.effect
+    /// Bytes sent for each pixel
+    pub fn get_channels(&self) -> usize {
+        self.output.channels()
+    }
+
+    pub fn get_pixels(&self) -> &Vec<Pixel> {
+        &self.pixels
+    }
+
+    pub fn get_current_effect(&self) -> &Effect {
+        &self.effect
     }
 
     pub fn get_current_effect_mut(&mut self) -> &mut Effect {
         &mut self.effect
     }
 
+    pub fn set_effect(&mut self, effect: Effect) {
+        self.effect = effect;
+    }
+
+    /// Colours of every pixel as RGB bytes scaled by `fade`, before any output
+    /// correction or brightness, as recordings are played back from
+    pub fn pixel_bytes(&self, fade: f32) -> Vec<u8> {
+        let fade = fade.clamp(0., 1.);
+        self.pixels
+            .iter()
+            .flat_map(|p| {
+                let rgb = Rgb::from(p.colour);
+                let (r, g, b) = Rgb::new(rgb.r * fade, rgb.g * fade, rgb.b * fade).to_bytes();
+                [r, g, b]
+            })
+            .collect()
+    }
+
     pub fn increase_brightness(&mut self) {
         self.max_brightness = (self.max_brightness + 0.05).clamp(0., 1.);
     }
@@ -104,21 +142,47 @@ impl PixelController {
         }
     }
 
-    pub fn transmit(&self, conn: &mut connection::DDPConnection) {
-        let values = self.pixels_to_arr();
+    /// Packs the current pixels into a frame ready to be sent, with the brightness
+    /// scaled by `fade` for fading in and out of standby
+    pub fn render_frame(&mut self, fade: f32) -> RenderedFrame {
+        let levels: Vec<[f32; 4]> = self
+            .pixels
+            .iter()
+            .map(|p| self.output.levels(p.colour))
+            .collect();
+
+        let (brightness, power) = self
+            .power
+            .limit(&levels, self.max_brightness * fade.clamp(0., 1.));
+
+        self.dither_error.resize(levels.len(), [0.; 4]);
 
-        let temp = conn.write(values.as_slice());
+        let mut data: Vec<u8> = Vec::with_capacity(levels.len() * self.output.channels());
+        for (pixel, error) in levels.into_iter().zip(self.dither_error.iter_mut()) {
+            self.output.pack(pixel, brightness, error, &mut data);
+        }
 
-        if temp.is_err() {
-            panic!("Something went Wrong");
+        RenderedFrame {
+            data,
+            power,
+            effect: self.effect.clone(),
+            brightness: self.max_brightness,
         }
     }
 
     pub fn update(&mut self, delta: f32) {
+        tempo::advance(delta);
         self.effect.render(&mut self.pixels);
         self.effect.update(delta, &self.pixels);
     }
 
+    /// A frame with every LED off. The dithering error is cleared first so nothing
+    /// left over from the last frame can round up to a lit LED
+    pub fn render_blank(&mut self) -> RenderedFrame {
+        self.dither_error.iter_mut().for_each(|e| *e = [0.; 4]);
+        self.render_frame(0.)
+    }
+
     pub fn save_settings(&self) {
         self.effect.save_settings();
 
@@ -131,11 +195,13 @@ impl PixelController {
             .with_section(None::<String>)
             .set(
                 "current_effect",
-                format!("{}", Effect::effect_to_id(self.effect)),
+                format!("{}", Effect::effect_to_id(&self.effect)),
             )
-            .set("brightness", format!("{:0.2}", self.max_brightness));
+            .set("brightness", format!("{:0.2}", self.max_brightness))
+            .set("bpm", format!("{:0.1}", tempo::bpm()))
+            .set("beats_per_bar", format!("{}", tempo::beats_per_bar()));
 
-        config.write_to_file(CONFIG_NAME).unwrap();
+        config::save(&config);
     }
 
     pub fn read_settings(&mut self) {
@@ -145,23 +211,18 @@ impl PixelController {
                     self.max_brightness = brightness.parse().unwrap();
                 }
 
+                if let Some(bpm) = section.get("bpm") {
+                    tempo::set_bpm(bpm.parse().unwrap());
+                }
+
+                if let Some(beats_per_bar) = section.get("beats_per_bar") {
+                    tempo::set_beats_per_bar(beats_per_bar.parse().unwrap());
+                }
+
                 if let Some(effect) = section.get("current_effect") {
                     self.effect = Effect::id_to_effect(effect.parse().unwrap());
                 }
             }
         }
     }
-
-    fn pixels_to_arr(&self) -> Vec<u8> {
-        let mut pixel_values: Vec<u8> = Vec::new();
-        for p in self.pixels.clone() {
-            let mut colour = p.colour;
-            colour.v = colour.v * self.max_brightness;
-            let (r, g, b) = Colour::to_rgb(&colour);
-            pixel_values.push(r);
-            pixel_values.push(g);
-            pixel_values.push(b);
-        }
-        pixel_values
-    }
 }
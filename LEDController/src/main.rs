@@ -1,21 +1,89 @@
 use std::io;
 
 pub mod app;
+pub mod audio;
+pub mod bytes;
 pub mod colour;
+pub mod config;
 pub mod effect;
+pub mod frame_file;
+pub mod fseq;
 pub mod led_controller;
+pub mod model_export;
+pub mod output;
+pub mod palette;
+pub mod pipeline;
 pub mod pixel;
+pub mod power;
+pub mod render;
+pub mod scheduler;
+pub mod tempo;
 pub mod vec3;
 
 use crate::app::App;
 // use crate::effect::effect_trait;
 
 const NUM_PIXELS: usize = 300;
-const FPS_MS: u64 = 20; // 60 FPS
+const FPS_MS: u64 = 20; // 50 FPS
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("render") {
+        return render(&args[2..]);
+    }
+    if args.get(1).map(String::as_str) == Some("export-model") {
+        return export_model(&args[2..]);
+    }
+
+    audio::start_from_settings();
+
+    ctrlc::set_handler(app::request_shutdown).expect("Couldn't set the signal handler");
+
     let mut terminal = ratatui::init();
     let app_result = App::new("192.168.0.163:4048", NUM_PIXELS, FPS_MS).run(&mut terminal);
     ratatui::restore();
     app_result
 }
+
+/// `render <effect> <seconds> <file> [fps]`, renders an effect to a file instead of
+/// running the controller
+fn render(args: &[String]) -> io::Result<()> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: render <effect> <seconds> <file> [fps]",
+        )
+    };
+
+    let [effect, seconds, path, rest @ ..] = args else {
+        return Err(usage());
+    };
+    let seconds: f32 = seconds.parse().map_err(|_| usage())?;
+    let fps: f32 = match rest.first() {
+        Some(fps) => fps.parse().map_err(|_| usage())?,
+        None => 1000. / FPS_MS as f32,
+    };
+
+    render::render_to_file(effect, seconds, fps, path, NUM_PIXELS)
+}
+
+/// `export-model <name> [cell size]`, writes the pixel layout as an xLights custom
+/// model and a 3D point model
+fn export_model(args: &[String]) -> io::Result<()> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Usage: export-model <name> [cell size]",
+        )
+    };
+
+    let [name, rest @ ..] = args else {
+        return Err(usage());
+    };
+    let cell_size: f32 = match rest.first() {
+        Some(cell_size) => cell_size.parse().map_err(|_| usage())?,
+        None => 10.,
+    };
+
+    model_export::export_model(name, cell_size, NUM_PIXELS)
+}
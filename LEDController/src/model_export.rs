@@ -0,0 +1,247 @@
+use std::fs;
+use std::io;
+
+use crate::led_controller::PixelController;
+use crate::output::OutputSettings;
+use crate::power::PowerModel;
+use crate::vec3::Vec3;
+
+/// Every cell of a custom model is laid out in xLights, so a grid bigger than
+/// this is no use and would take gigabytes to build
+const MAX_CELLS: usize = 10_000_000;
+
+/// Where each pixel ends up in the grid, as column, row and layer
+type Cell = (usize, usize, usize);
+
+/// What came out of fitting the pixels to a grid
+pub struct ModelGrid {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    /// The cell of each pixel, in string order
+    pub cells: Vec<Cell>,
+    /// Pixels that shared a cell with another and were moved to the nearest free one
+    pub moved: usize,
+}
+
+impl ModelGrid {
+    /// Snaps each position to a grid of `cell_size` cubes. Columns run along x,
+    /// rows down from the top of the tree and layers along z, as xLights expects.
+    /// Fails if the grid has fewer cells than there are pixels, or far too many
+    pub fn quantise(positions: &[Vec3], cell_size: f32) -> io::Result<ModelGrid> {
+        let min = positions
+            .iter()
+            .fold(Vec3::new(f32::MAX, f32::MAX, f32::MAX), |m, p| {
+                Vec3::new(m.x.min(p.x), m.y.min(p.y), m.z.min(p.z))
+            });
+        let max = positions
+            .iter()
+            .fold(Vec3::new(f32::MIN, f32::MIN, f32::MIN), |m, p| {
+                Vec3::new(m.x.max(p.x), m.y.max(p.y), m.z.max(p.z))
+            });
+
+        // Continuous grid position of each pixel
+        let scaled: Vec<[f32; 3]> = positions
+            .iter()
+            .map(|p| {
+                [
+                    (p.x - min.x) / cell_size,
+                    (max.y - p.y) / cell_size,
+                    (p.z - min.z) / cell_size,
+                ]
+            })
+            .collect();
+
+        let size = |extent: f32| (extent / cell_size).round() as usize + 1;
+        let mut grid = ModelGrid {
+            width: size(max.x - min.x),
+            height: size(max.y - min.y),
+            depth: size(max.z - min.z),
+            cells: Vec::with_capacity(positions.len()),
+            moved: 0,
+        };
+
+        let cell_count = grid
+            .width
+            .checked_mul(grid.height)
+            .and_then(|count| count.checked_mul(grid.depth))
+            .filter(|&count| count <= MAX_CELLS)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "A cell size of {} gives more than {} cells, try a larger cell size",
+                        cell_size, MAX_CELLS
+                    ),
+                )
+            })?;
+        if cell_count < positions.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "A cell size of {} only gives {} cells for {} pixels, try a smaller cell size",
+                    cell_size,
+                    cell_count,
+                    positions.len()
+                ),
+            ));
+        }
+
+        let mut taken = vec![false; cell_count];
+        for position in scaled {
+            let wanted = position.map(|c| c.round() as i64);
+            let cell = grid
+                .nearest_free(&taken, wanted, position)
+                .ok_or_else(|| io::Error::other("No free cell left in the model grid"))?;
+            if cell != (wanted[0] as usize, wanted[1] as usize, wanted[2] as usize) {
+                grid.moved += 1;
+            }
+
+            taken[grid.index(cell)] = true;
+            grid.cells.push(cell);
+        }
+
+        Ok(grid)
+    }
+
+    fn index(&self, (column, row, layer): Cell) -> usize {
+        (layer * self.height + row) * self.width + column
+    }
+
+    /// The free cell closest to `position`, searching outwards from `wanted` one
+    /// shell at a time, or None if every cell is taken
+    fn nearest_free(&self, taken: &[bool], wanted: [i64; 3], position: [f32; 3]) -> Option<Cell> {
+        let limits = [self.width, self.height, self.depth].map(|l| l as i64);
+        let max_radius = limits.iter().copied().max().unwrap_or(1);
+
+        for radius in 0..=max_radius {
+            let mut best: Option<(f32, Cell)> = None;
+
+            for dz in -radius..=radius {
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        if dx.abs().max(dy.abs()).max(dz.abs()) != radius {
+                            continue;
+                        }
+
+                        let cell = [wanted[0] + dx, wanted[1] + dy, wanted[2] + dz];
+                        if cell.iter().zip(limits).any(|(c, l)| *c < 0 || *c >= l) {
+                            continue;
+                        }
+
+                        let cell: Cell = (cell[0] as usize, cell[1] as usize, cell[2] as usize);
+                        if taken[self.index(cell)] {
+                            continue;
+                        }
+
+                        let distance = (cell.0 as f32 - position[0]).powi(2)
+                            + (cell.1 as f32 - position[1]).powi(2)
+                            + (cell.2 as f32 - position[2]).powi(2);
+                        if best.is_none_or(|(d, _)| distance < d) {
+                            best = Some((distance, cell));
+                        }
+                    }
+                }
+            }
+
+            if let Some((_, cell)) = best {
+                return Some(cell);
+            }
+        }
+
+        None
+    }
+
+    /// The `CustomModel` attribute of an xLights custom model. Layers are separated
+    /// by `|`, rows by `;` and columns by `,`, with each pixel's node number, from 1,
+    /// in its cell
+    fn custom_model_data(&self) -> String {
+        let mut nodes = vec![0; self.width * self.height * self.depth];
+        for (i, cell) in self.cells.iter().enumerate() {
+            nodes[self.index(*cell)] = i + 1;
+        }
+
+        (0..self.depth)
+            .map(|layer| {
+                (0..self.height)
+                    .map(|row| {
+                        (0..self.width)
+                            .map(|column| match nodes[self.index((column, row, layer))] {
+                                0 => String::new(),
+                                node => node.to_string(),
+                            })
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";")
+            })
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+
+    pub fn to_xmodel(&self, name: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <custommodel\n\
+             name=\"{}\" parm1=\"{}\" parm2=\"{}\" Depth=\"{}\" StringType=\"RGB Nodes\" \
+             Transparency=\"0\" PixelSize=\"2\" ModelBrightness=\"\" Antialias=\"1\" \
+             StrandNames=\"\" NodeNames=\"\" CustomModel=\"{}\" >\n\
+             </custommodel>\n",
+            name.replace('&', "&amp;")
+                .replace('"', "&quot;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            self.width,
+            self.height,
+            self.depth,
+            self.custom_model_data()
+        )
+    }
+}
+
+/// A Wavefront OBJ holding one vertex per pixel at its measured position, in
+/// string order, which can be imported as a 3D reference alongside the model
+fn to_point_model(name: &str, positions: &[Vec3]) -> String {
+    let mut obj = format!("# {} pixel positions in string order\no {}\n", name, name);
+    for p in positions {
+        obj.push_str(&format!("v {:.3} {:.3} {:.3}\n", p.x, p.y, p.z));
+    }
+    obj
+}
+
+/// Writes `<name>.xmodel` and `<name>.obj` from the pixel positions in `Output.pixels`
+pub fn export_model(name: &str, cell_size: f32, pixel_count: usize) -> io::Result<()> {
+    if cell_size <= 0. {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The cell size must be above 0",
+        ));
+    }
+
+    let controller = PixelController::new(
+        pixel_count,
+        OutputSettings::default(),
+        PowerModel::default(),
+    );
+    let positions: Vec<Vec3> = controller.get_pixels().iter().map(|p| p.position).collect();
+
+    let grid = ModelGrid::quantise(&positions, cell_size)?;
+
+    let model_path = format!("{}.xmodel", name);
+    let points_path = format!("{}.obj", name);
+    fs::write(&model_path, grid.to_xmodel(name))?;
+    fs::write(&points_path, to_point_model(name, &positions))?;
+
+    println!(
+        "Exported {} pixels to {} ({} x {} x {}, {} moved to a neighbouring cell) and {}",
+        positions.len(),
+        model_path,
+        grid.width,
+        grid.height,
+        grid.depth,
+        grid.moved,
+        points_path
+    );
+    Ok(())
+}
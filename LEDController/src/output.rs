@@ -0,0 +1,299 @@
+use ddp_rs::protocol;
+use ini::Ini;
+
+use crate::colour::*;
+use crate::effect::constants::CONFIG_NAME;
+
+/// Settings shared by every destination live in `[Output]`, with overrides for a
+/// single destination in `[Output.<address>]`, e.g. `[Output.192.168.0.163:4048]`
+const SECTION: &str = "Output";
+
+/// The order a strip expects its colour channels to arrive in
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColourOrder {
+    Rgb,
+    Rbg,
+    Grb,
+    Gbr,
+    Brg,
+    Bgr,
+}
+
+impl ColourOrder {
+    pub fn from_string(name: &str) -> Option<ColourOrder> {
+        match name.trim().to_uppercase().as_str() {
+            "RGB" => Some(ColourOrder::Rgb),
+            "RBG" => Some(ColourOrder::Rbg),
+            "GRB" => Some(ColourOrder::Grb),
+            "GBR" => Some(ColourOrder::Gbr),
+            "BRG" => Some(ColourOrder::Brg),
+            "BGR" => Some(ColourOrder::Bgr),
+            _ => None,
+        }
+    }
+
+    pub fn arrange<T>(self, r: T, g: T, b: T) -> [T; 3] {
+        match self {
+            ColourOrder::Rgb => [r, g, b],
+            ColourOrder::Rbg => [r, b, g],
+            ColourOrder::Grb => [g, r, b],
+            ColourOrder::Gbr => [g, b, r],
+            ColourOrder::Brg => [b, r, g],
+            ColourOrder::Bgr => [b, g, r],
+        }
+    }
+}
+
+/// How the white channel of RGBW strips is driven
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WhiteMode {
+    /// Plain RGB strips with no white channel
+    None,
+    /// Moves the part shared by all three channels onto the white LED
+    Subtract,
+    /// Like subtract, but takes into account the tint of the white LED
+    Temperature,
+    /// Uses only the white LED for colours below a saturation threshold
+    /// and only the RGB LEDs for everything else
+    Desaturated,
+}
+
+impl WhiteMode {
+    pub fn from_string(name: &str) -> Option<WhiteMode> {
+        match name.trim().to_lowercase().as_str() {
+            "none" => Some(WhiteMode::None),
+            "subtract" => Some(WhiteMode::Subtract),
+            "temperature" => Some(WhiteMode::Temperature),
+            "desaturated" => Some(WhiteMode::Desaturated),
+            _ => None,
+        }
+    }
+}
+
+/// Corrections applied to every colour just before it is sent, to make up for how
+/// the LEDs actually respond
+#[derive(Clone, Debug)]
+pub struct OutputSettings {
+    /// Per channel exponent, 1 sends the colours unchanged
+    pub gamma: [f32; 3],
+    /// Per channel scale applied after gamma, to balance the white point of the LEDs
+    pub white_point: [f32; 3],
+    pub colour_order: ColourOrder,
+    pub white: WhiteMode,
+    /// Colour temperature of the white LED in kelvin, used by `WhiteMode::Temperature`
+    pub white_temperature: f32,
+    /// Saturation below which `WhiteMode::Desaturated` switches to the white LED
+    pub white_threshold: f32,
+    /// Carries rounding errors over to the next frame so levels between two byte
+    /// values average out to the right brightness
+    pub dither: bool,
+    /// The white LED's colour as RGB, worked out from `white_temperature`
+    white_led: [f32; 3],
+}
+
+impl OutputSettings {
+    pub fn default() -> OutputSettings {
+        OutputSettings {
+            gamma: [1.; 3],
+            white_point: [1.; 3],
+            colour_order: ColourOrder::Rgb,
+            white: WhiteMode::None,
+            white_temperature: 4500.,
+            white_threshold: 0.2,
+            dither: false,
+            white_led: kelvin_to_rgb(4500.),
+        }
+    }
+
+    /// Reads the shared settings then the overrides for `destination`
+    pub fn load(destination: &str) -> OutputSettings {
+        let mut settings = OutputSettings::default();
+
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            settings.read_section(&config, SECTION);
+            settings.read_section(&config, &format!("{}.{}", SECTION, destination));
+        }
+
+        settings.white_led = kelvin_to_rgb(settings.white_temperature);
+        settings
+    }
+
+    fn read_section(&mut self, config: &Ini, name: &str) {
+        let Some(section) = config.section(Some(name)) else {
+            return;
+        };
+
+        if let Some(gamma) = section.get("gamma").and_then(parse_channels) {
+            self.gamma = gamma;
+        }
+
+        if let Some(white_point) = section.get("white_point").and_then(parse_channels) {
+            self.white_point = white_point.map(|c| c.clamp(0., 1.));
+        }
+
+        if let Some(colour_order) = section
+            .get("colour_order")
+            .and_then(ColourOrder::from_string)
+        {
+            self.colour_order = colour_order;
+        }
+
+        if let Some(white) = section.get("white").and_then(WhiteMode::from_string) {
+            self.white = white;
+        }
+
+        if let Some(white_temperature) = section.get("white_temperature") {
+            self.white_temperature = white_temperature.parse().unwrap();
+        }
+
+        if let Some(white_threshold) = section.get("white_threshold") {
+            self.white_threshold = white_threshold.parse().unwrap();
+        }
+
+        if let Some(dither) = section.get("dither") {
+            self.dither = dither.parse().unwrap();
+        }
+    }
+
+    /// Bytes sent for each pixel, 4 when driving a white channel
+    pub fn channels(&self) -> usize {
+        match self.white {
+            WhiteMode::None => 3,
+            _ => 4,
+        }
+    }
+
+    /// Tells the receiver how many bytes make up each pixel
+    pub fn ddp_pixel_config(&self) -> protocol::PixelConfig {
+        match self.channels() {
+            4 => protocol::PixelConfig {
+                data_type: protocol::pixel_config::DataType::RGBW,
+                data_size: protocol::PixelFormat::Pixel32Bits,
+                customer_defined: false,
+            },
+            _ => protocol::PixelConfig::default(),
+        }
+    }
+
+    /// Drive level of each LED in a pixel from 0 - 1 before brightness is applied,
+    /// as red, green, blue and white. White is always 0 without a white channel
+    pub fn levels(&self, colour: Colour) -> [f32; 4] {
+        let rgb = Rgb::from(colour);
+        let mut channels = [rgb.r, rgb.g, rgb.b];
+        for (channel, gamma) in channels.iter_mut().zip(self.gamma) {
+            *channel = channel.clamp(0., 1.).powf(gamma);
+        }
+
+        let white = self.extract_white(colour, &mut channels).unwrap_or(0.);
+
+        [
+            channels[0] * self.white_point[0],
+            channels[1] * self.white_point[1],
+            channels[2] * self.white_point[2],
+            white,
+        ]
+    }
+
+    /// Adds the bytes for one pixel to `out`, scaling it by `brightness` after gamma
+    /// so dimming the whole output doesn't change the colour balance. `error` holds
+    /// this pixel's rounding error from the previous frame when dithering
+    pub fn pack(&self, levels: [f32; 4], brightness: f32, error: &mut [f32; 4], out: &mut Vec<u8>) {
+        let [r, g, b, w] = std::array::from_fn(|i| {
+            let target = 255. * levels[i] * brightness;
+            if !self.dither {
+                return target.round().clamp(0., 255.) as u8;
+            }
+
+            let dithered = target + error[i];
+            let byte = dithered.round().clamp(0., 255.);
+            error[i] = dithered - byte;
+            byte as u8
+        });
+
+        out.extend(self.colour_order.arrange(r, g, b));
+        if self.channels() == 4 {
+            out.push(w);
+        }
+    }
+
+    /// Takes the white channel's share out of `channels`, returning its level
+    fn extract_white(&self, colour: Colour, channels: &mut [f32; 3]) -> Option<f32> {
+        match self.white {
+            WhiteMode::None => None,
+            WhiteMode::Subtract => {
+                let white = channels.iter().copied().fold(1., f32::min);
+                for channel in channels.iter_mut() {
+                    *channel -= white;
+                }
+                Some(white)
+            }
+            WhiteMode::Temperature => {
+                // The most of the white LED's own colour that fits inside the colour.
+                // Channels the LED doesn't emit at all place no limit on it
+                let white = channels
+                    .iter()
+                    .zip(self.white_led)
+                    .filter(|(_, led)| *led > 0.)
+                    .map(|(channel, led)| channel / led)
+                    .fold(1., f32::min);
+
+                for (channel, led) in channels.iter_mut().zip(self.white_led) {
+                    *channel = (*channel - white * led).max(0.);
+                }
+                Some(white)
+            }
+            WhiteMode::Desaturated => {
+                if colour.s < self.white_threshold {
+                    let white = channels.iter().copied().fold(0., f32::max);
+                    *channels = [0.; 3];
+                    Some(white)
+                } else {
+                    Some(0.)
+                }
+            }
+        }
+    }
+}
+
+/// Approximate colour of a black body at `kelvin`, scaled so the brightest channel is 1
+fn kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin.clamp(1000., 40000.) / 100.;
+
+    let r = if t <= 66. {
+        255.
+    } else {
+        329.69873 * (t - 60.).powf(-0.133_204_76)
+    };
+
+    let g = if t <= 66. {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12216 * (t - 60.).powf(-0.075_514_85)
+    };
+
+    let b = if t >= 66. {
+        255.
+    } else if t <= 19. {
+        0.
+    } else {
+        138.51773 * (t - 10.).ln() - 305.0448
+    };
+
+    let rgb = [r, g, b].map(|c| c.clamp(0., 255.));
+    let max = rgb.iter().copied().fold(1., f32::max);
+    rgb.map(|c| c / max)
+}
+
+/// Parses either one value for all channels or three space separated values for R, G and B
+fn parse_channels(text: &str) -> Option<[f32; 3]> {
+    let values: Vec<f32> = text
+        .split_whitespace()
+        .map(|v| v.parse().ok())
+        .collect::<Option<_>>()?;
+
+    match values.as_slice() {
+        [all] => Some([*all; 3]),
+        [r, g, b] => Some([*r, *g, *b]),
+        _ => None,
+    }
+}
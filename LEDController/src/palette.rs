@@ -0,0 +1,301 @@
+use ini::Ini;
+
+use crate::colour::*;
+use crate::effect::constants::CONFIG_NAME;
+
+/// User palettes are read from sections named `Palette.<name>` in the config
+const SECTION_PREFIX: &str = "Palette.";
+
+#[derive(Copy, Clone)]
+pub struct ColourStop {
+    pub position: f32,
+    pub colour: Colour,
+}
+
+/// How the colours between two stops are worked out
+#[derive(Copy, Clone, PartialEq)]
+pub enum BlendSpace {
+    Rgb,
+    /// Shortest way around the hue wheel, giving fully saturated colours between hues
+    Hsv,
+    /// Perceptually even steps in brightness and colour
+    OkLab,
+}
+
+impl BlendSpace {
+    pub fn from_string(name: &str) -> Option<BlendSpace> {
+        match name.trim().to_lowercase().as_str() {
+            "rgb" => Some(BlendSpace::Rgb),
+            "hsv" => Some(BlendSpace::Hsv),
+            "oklab" => Some(BlendSpace::OkLab),
+            _ => None,
+        }
+    }
+
+    pub fn blend(self, from: Colour, to: Colour, t: f32) -> Colour {
+        match self {
+            BlendSpace::Rgb => Colour::from(Rgb::lerp(Rgb::from(from), Rgb::from(to), t)),
+            BlendSpace::Hsv => Colour::lerp(from, to, t),
+            BlendSpace::OkLab => Colour::blend(from, to, t),
+        }
+    }
+}
+
+/// A gradient through any number of colour stops, sampled by a position from 0 - 1
+#[derive(Clone)]
+pub struct Palette {
+    name: String,
+    stops: Vec<ColourStop>,
+    blend_space: BlendSpace,
+}
+
+impl Palette {
+    pub fn new(name: &str, mut stops: Vec<ColourStop>) -> Palette {
+        stops.retain(|stop| stop.position.is_finite());
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        for stop in stops.iter_mut() {
+            stop.position = stop.position.clamp(0., 1.);
+        }
+
+        Palette {
+            name: name.to_string(),
+            stops,
+            blend_space: BlendSpace::OkLab,
+        }
+    }
+
+    pub fn with_blend_space(mut self, blend_space: BlendSpace) -> Palette {
+        self.blend_space = blend_space;
+        self
+    }
+
+    /// Builds a palette from colours spread evenly from 0 up to, but not including, 1
+    /// so that wrapping back round to the first colour is as long as any other step
+    fn evenly_spaced(name: &str, colours: &[Colour]) -> Palette {
+        let stops = colours
+            .iter()
+            .enumerate()
+            .map(|(i, colour)| ColourStop {
+                position: i as f32 / colours.len() as f32,
+                colour: *colour,
+            })
+            .collect();
+        Palette::new(name, stops)
+    }
+
+    fn from_hex(name: &str, colours: &[u32]) -> Palette {
+        let colours: Vec<Colour> = colours.iter().map(|hex| hex_colour(*hex)).collect();
+        Palette::evenly_spaced(name, &colours)
+    }
+
+    /// The full hue wheel, matching the fully saturated colours effects used before palettes
+    pub fn rainbow() -> Palette {
+        Palette::evenly_spaced("Rainbow", &[RED, YELLOW, GREEN, CYAN, BLUE, PINK])
+            .with_blend_space(BlendSpace::Hsv)
+    }
+
+    pub fn fire() -> Palette {
+        Palette::new(
+            "Fire",
+            vec![
+                ColourStop {
+                    position: 0.,
+                    colour: BLACK,
+                },
+                ColourStop {
+                    position: 0.35,
+                    colour: RED,
+                },
+                ColourStop {
+                    position: 0.6,
+                    colour: Colour::new(30., 1., 1.),
+                },
+                ColourStop {
+                    position: 0.8,
+                    colour: YELLOW,
+                },
+                ColourStop {
+                    position: 1.,
+                    colour: WHITE,
+                },
+            ],
+        )
+    }
+
+    pub fn ocean() -> Palette {
+        Palette::from_hex(
+            "Ocean",
+            &[0x000050, 0x0000FF, 0x0080FF, 0x00FFFF, 0x40C0A0, 0x0040A0],
+        )
+    }
+
+    pub fn forest() -> Palette {
+        Palette::from_hex(
+            "Forest",
+            &[0x004000, 0x00A000, 0x60B000, 0x808000, 0x604000, 0x206000],
+        )
+    }
+
+    pub fn party() -> Palette {
+        Palette::from_hex(
+            "Party",
+            &[
+                0x5500AB, 0xB5004B, 0xE81700, 0xAB7700, 0xABAB00, 0xDD2200, 0xC2003E, 0x5F00A1,
+                0x0007F9,
+            ],
+        )
+    }
+
+    pub fn candy_cane() -> Palette {
+        Palette::new(
+            "Candy Cane",
+            vec![
+                ColourStop {
+                    position: 0.,
+                    colour: RED,
+                },
+                ColourStop {
+                    position: 0.4,
+                    colour: RED,
+                },
+                ColourStop {
+                    position: 0.5,
+                    colour: WHITE,
+                },
+                ColourStop {
+                    position: 0.9,
+                    colour: WHITE,
+                },
+            ],
+        )
+    }
+
+    pub fn built_in() -> Vec<Palette> {
+        vec![
+            Palette::rainbow(),
+            Palette::fire(),
+            Palette::ocean(),
+            Palette::forest(),
+            Palette::party(),
+            Palette::candy_cane(),
+        ]
+    }
+
+    /// Every built in palette followed by any defined in the config, e.g.
+    /// `[Palette.Sunset]` with `stops = 0 #200040, 0.5 #FF4000, 1 #FFD080`. They are
+    /// blended in OKLab unless `blend` is set to `rgb` or `hsv`
+    pub fn all() -> Vec<Palette> {
+        let mut palettes = Palette::built_in();
+
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            for (name, section) in config.iter() {
+                let Some(name) = name.and_then(|n| n.strip_prefix(SECTION_PREFIX)) else {
+                    continue;
+                };
+
+                if let Some(stops) = section.get("stops").and_then(parse_stops) {
+                    let blend_space = section
+                        .get("blend")
+                        .and_then(BlendSpace::from_string)
+                        .unwrap_or(BlendSpace::OkLab);
+
+                    palettes.retain(|p| p.name != name);
+                    palettes.push(Palette::new(name, stops).with_blend_space(blend_space));
+                }
+            }
+        }
+
+        palettes
+    }
+
+    /// Looks a palette up by name, falling back to the rainbow if it no longer exists
+    pub fn by_name(name: &str) -> Palette {
+        Palette::all()
+            .into_iter()
+            .find(|p| p.name == name)
+            .unwrap_or_else(Palette::rainbow)
+    }
+
+    /// The palette after this one in `all`, for cycling through them from the TUI
+    pub fn next(&self) -> Palette {
+        let mut palettes = Palette::all();
+        let index = palettes
+            .iter()
+            .position(|p| p.name == self.name)
+            .map_or(0, |i| (i + 1) % palettes.len());
+        palettes.swap_remove(index)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Colour at `t`, wrapping so 1 is back at the start and blending from the
+    /// last stop round to the first
+    pub fn sample(&self, t: f32) -> Colour {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return BLACK;
+        };
+
+        let t = t.rem_euclid(1.);
+        if t < first.position || t >= last.position {
+            let span = first.position + 1. - last.position;
+            let from_last = (t - last.position).rem_euclid(1.);
+            let blend = if span > 0. { from_last / span } else { 0. };
+            return self.blend_space.blend(last.colour, first.colour, blend);
+        }
+
+        self.between_stops(t)
+    }
+
+    /// Colour at `t`, holding the first and last stops outside 0 - 1
+    pub fn sample_clamped(&self, t: f32) -> Colour {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return BLACK;
+        };
+
+        let t = t.clamp(0., 1.);
+        if t <= first.position {
+            first.colour
+        } else if t >= last.position {
+            last.colour
+        } else {
+            self.between_stops(t)
+        }
+    }
+
+    /// Blends the two stops either side of `t`, which must be within the stops
+    fn between_stops(&self, t: f32) -> Colour {
+        let next = self.stops.partition_point(|stop| stop.position <= t);
+        let from = self.stops[next - 1];
+        let to = self.stops[next];
+
+        let span = to.position - from.position;
+        let blend = if span > 0. {
+            (t - from.position) / span
+        } else {
+            0.
+        };
+        self.blend_space.blend(from.colour, to.colour, blend)
+    }
+}
+
+fn hex_colour(hex: u32) -> Colour {
+    Colour::from_rgb((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+/// Parses a comma separated list of `position #RRGGBB` pairs
+fn parse_stops(text: &str) -> Option<Vec<ColourStop>> {
+    let stops: Option<Vec<ColourStop>> = text
+        .split(',')
+        .map(|stop| {
+            let mut parts = stop.split_whitespace();
+            let position = parts.next()?.parse().ok()?;
+            let hex = parts.next()?.trim_start_matches('#');
+            let colour = hex_colour(u32::from_str_radix(hex, 16).ok()?);
+            Some(ColourStop { position, colour })
+        })
+        .collect();
+
+    stops.filter(|stops| !stops.is_empty())
+}
@@ -0,0 +1,103 @@
+use std::net::UdpSocket;
+
+use arc_swap::ArcSwap;
+use ddp_rs::{connection, protocol};
+use ini::Ini;
+
+use crate::effect::constants::CONFIG_NAME;
+use crate::effect::effect_list::Effect;
+use crate::power::PowerReport;
+
+const STANDBY_SECTION: &str = "Standby";
+
+/// A complete frame, ready to send. Frames are never changed once rendered, so
+/// the output and the TUI can hold on to one while the next is being made
+pub struct RenderedFrame {
+    /// Packed channel bytes for every pixel, as sent
+    pub data: Vec<u8>,
+    pub power: PowerReport,
+    /// The effect as it was when the frame was rendered, which the TUI draws from
+    /// so it never has to wait on the controller
+    pub effect: Effect,
+    pub brightness: f32,
+}
+
+/// The most recently rendered frame. Swapping in a new frame never blocks readers
+/// and readers never see a frame that is only partly written
+pub type FrameBuffer = ArcSwap<RenderedFrame>;
+
+pub fn new_frame_buffer(first: RenderedFrame) -> FrameBuffer {
+    ArcSwap::from_pointee(first)
+}
+
+pub fn send(frame: &RenderedFrame, conn: &mut connection::DDPConnection) {
+    if conn.write(frame.data.as_slice()).is_err() {
+        panic!("Something went Wrong");
+    }
+}
+
+/// Sends a single black frame of `bytes` over a connection of its own. For when
+/// the pipeline thread can't be relied on, so any error is ignored
+pub fn blank(destination: &str, pixel_config: protocol::PixelConfig, bytes: usize) {
+    let Ok(socket) = UdpSocket::bind("0.0.0.0:0") else {
+        return;
+    };
+
+    if let Ok(mut conn) =
+        connection::DDPConnection::try_new(destination, pixel_config, protocol::ID::Default, socket)
+    {
+        let _ = conn.write(&vec![0; bytes]);
+    }
+}
+
+/// How the output goes into and comes out of standby when disabled
+#[derive(Copy, Clone, Debug)]
+pub struct StandbySettings {
+    /// Time taken to fade out when disabled and back in when enabled, 0 switches instantly
+    pub fade_seconds: f32,
+    /// How often black is resent while in standby, so controllers that fall back
+    /// to their own effects when nothing arrives stay dark. 0 sends black once
+    pub keep_alive_seconds: f32,
+}
+
+impl StandbySettings {
+    pub fn default() -> StandbySettings {
+        StandbySettings {
+            fade_seconds: 1.,
+            keep_alive_seconds: 2.,
+        }
+    }
+
+    pub fn load() -> StandbySettings {
+        let mut settings = StandbySettings::default();
+
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            if let Some(section) = config.section(Some(STANDBY_SECTION)) {
+                if let Some(fade_seconds) = section.get("fade_seconds") {
+                    settings.fade_seconds = fade_seconds.parse().unwrap();
+                }
+
+                if let Some(keep_alive_seconds) = section.get("keep_alive_seconds") {
+                    settings.keep_alive_seconds = keep_alive_seconds.parse().unwrap();
+                }
+            }
+        }
+
+        settings
+    }
+
+    /// Moves `level` towards fully on or fully off over `delta` seconds
+    pub fn fade(&self, level: f32, enabled: bool, delta: f32) -> f32 {
+        let target = if enabled { 1. } else { 0. };
+        if self.fade_seconds <= 0. {
+            return target;
+        }
+
+        let step = delta / self.fade_seconds;
+        if enabled {
+            (level + step).min(target)
+        } else {
+            (level - step).max(target)
+        }
+    }
+}
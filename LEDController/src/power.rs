@@ -0,0 +1,128 @@
+use ini::Ini;
+
+use crate::effect::constants::CONFIG_NAME;
+
+/// Read from the same sections as the output settings, so each destination can
+/// have its own supply
+const SECTION: &str = "Output";
+
+/// Estimates the current drawn by a frame and how far it has to be dimmed to stay
+/// within what the supply can provide
+#[derive(Clone, Debug)]
+pub struct PowerModel {
+    /// Current drawn by one LED of each channel at full duty, as red, green, blue and white
+    pub channel_milliamps: [f32; 4],
+    /// Current drawn by each pixel's driver even when it is off
+    pub idle_milliamps: f32,
+    /// What the supply can provide, 0 turns the limit off
+    pub supply_amps: f32,
+}
+
+/// What the limiter did to the last frame
+#[derive(Copy, Clone, Debug)]
+pub struct PowerReport {
+    /// Estimated draw of the frame as sent, including idle draw
+    pub milliamps: f32,
+    /// How much the brightness was scaled down by, 1 when within budget
+    pub scale: f32,
+    /// What the supply can provide, 0 when there is no limit
+    pub supply_amps: f32,
+}
+
+impl PowerReport {
+    pub fn default() -> PowerReport {
+        PowerReport {
+            milliamps: 0.,
+            scale: 1.,
+            supply_amps: 0.,
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.scale < 1.
+    }
+}
+
+impl PowerModel {
+    /// Typical WS2812 and SK6812 figures of 20mA per channel and 1mA idle, with no limit
+    pub fn default() -> PowerModel {
+        PowerModel {
+            channel_milliamps: [20.; 4],
+            idle_milliamps: 1.,
+            supply_amps: 0.,
+        }
+    }
+
+    /// Reads the shared settings then the overrides for `destination`
+    pub fn load(destination: &str) -> PowerModel {
+        let mut model = PowerModel::default();
+
+        if let Ok(config) = Ini::load_from_file(CONFIG_NAME) {
+            model.read_section(&config, SECTION);
+            model.read_section(&config, &format!("{}.{}", SECTION, destination));
+        }
+
+        model
+    }
+
+    fn read_section(&mut self, config: &Ini, name: &str) {
+        let Some(section) = config.section(Some(name)) else {
+            return;
+        };
+
+        if let Some(milliamps) = section.get("channel_milliamps") {
+            let values: Vec<f32> = milliamps
+                .split_whitespace()
+                .map(|v| v.parse().unwrap())
+                .collect();
+
+            match values.as_slice() {
+                [all] => self.channel_milliamps = [*all; 4],
+                [r, g, b] => self.channel_milliamps = [*r, *g, *b, self.channel_milliamps[3]],
+                [r, g, b, w] => self.channel_milliamps = [*r, *g, *b, *w],
+                _ => {}
+            }
+        }
+
+        if let Some(idle_milliamps) = section.get("idle_milliamps") {
+            self.idle_milliamps = idle_milliamps.parse().unwrap();
+        }
+
+        if let Some(supply_amps) = section.get("supply_amps") {
+            self.supply_amps = supply_amps.parse().unwrap();
+        }
+    }
+
+    /// Works out the brightness to send `levels` at so the estimated draw stays
+    /// within the supply, no brighter than `brightness`
+    pub fn limit(&self, levels: &[[f32; 4]], brightness: f32) -> (f32, PowerReport) {
+        let idle = self.idle_milliamps * levels.len() as f32;
+        let full: f32 = levels
+            .iter()
+            .map(|pixel| {
+                pixel
+                    .iter()
+                    .zip(self.channel_milliamps)
+                    .map(|(level, milliamps)| level * milliamps)
+                    .sum::<f32>()
+            })
+            .sum();
+
+        let requested = full * brightness;
+        let budget = self.supply_amps * 1000. - idle;
+
+        let scale = if self.supply_amps > 0. && requested > budget {
+            (budget / requested).max(0.)
+        } else {
+            1.
+        };
+
+        let report = PowerReport {
+            milliamps: idle + requested * scale,
+            scale,
+            supply_amps: self.supply_amps,
+        };
+
+        (brightness * scale, report)
+    }
+}
@@ -0,0 +1,60 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use crate::effect::effect_list::Effect;
+use crate::frame_file::FrameWriter;
+use crate::led_controller::PixelController;
+use crate::output::OutputSettings;
+use crate::power::PowerModel;
+
+/// Runs an effect with a fixed timestep, without any hardware, and writes every
+/// frame to `path`. The effect's saved settings are used
+pub fn render_to_file(
+    effect_name: &str,
+    seconds: f32,
+    fps: f32,
+    path: &str,
+    pixel_count: usize,
+) -> io::Result<()> {
+    let Some(effect_id) = Effect::name_to_id(effect_name) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("No effect called {}", effect_name),
+        ));
+    };
+
+    let mut controller = PixelController::new(
+        pixel_count,
+        OutputSettings::default(),
+        PowerModel::default(),
+    );
+    controller.set_effect(Effect::id_to_effect(effect_id));
+
+    let mut writer = FrameWriter::create(path, pixel_count, fps)?;
+
+    // Files are loaded in the background, so wait for them rather than
+    // rendering black until they are ready
+    controller.update(0.);
+    while controller.get_current_effect().is_loading() {
+        thread::sleep(Duration::from_millis(10));
+        controller.update(0.);
+    }
+
+    let delta = 1. / fps;
+    let frames = (seconds * fps).round() as usize;
+    for _ in 0..frames {
+        controller.update(delta);
+        writer.write_frame(&controller.pixel_bytes(1.))?;
+    }
+
+    writer.finish()?;
+
+    println!(
+        "Rendered {} frames of {} to {}",
+        frames,
+        controller.get_current_effect().to_string(),
+        path
+    );
+    Ok(())
+}
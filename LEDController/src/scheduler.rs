@@ -0,0 +1,111 @@
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much each new measurement moves the displayed averages
+const SMOOTHING: f32 = 0.1;
+
+/// Paces a loop to a fixed rate. Frames are scheduled against deadlines rather
+/// than sleeping a whole period after the work, so time spent working doesn't
+/// slow the rate down
+pub struct FrameScheduler {
+    period: Duration,
+    next_frame: Instant,
+    last_frame: Instant,
+}
+
+impl FrameScheduler {
+    pub fn new(period: Duration) -> FrameScheduler {
+        let now = Instant::now();
+        FrameScheduler {
+            period,
+            next_frame: now + period,
+            last_frame: now,
+        }
+    }
+
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Sleeps until the next frame is due, returning the time since the last one
+    pub fn wait(&mut self) -> Duration {
+        let now = Instant::now();
+        if self.next_frame > now {
+            thread::sleep(self.next_frame - now);
+        }
+
+        let now = Instant::now();
+        // After a long stall start afresh rather than rushing to catch up
+        if now > self.next_frame + self.period {
+            self.next_frame = now;
+        }
+        self.next_frame += self.period;
+
+        let delta = now - self.last_frame;
+        self.last_frame = now;
+        delta
+    }
+}
+
+/// Smoothed timings of the output loops, for display
+#[derive(Copy, Clone, Debug)]
+pub struct FrameStats {
+    pub target_fps: f32,
+    pub fps: f32,
+    /// Time taken to update and render the effect, in milliseconds
+    pub render_ms: f32,
+    /// Time taken to pack and send a frame, in milliseconds
+    pub transmit_ms: f32,
+    /// Average distance of each frame from when it was due, in milliseconds
+    pub jitter_ms: f32,
+}
+
+impl FrameStats {
+    const fn new() -> FrameStats {
+        FrameStats {
+            target_fps: 0.,
+            fps: 0.,
+            render_ms: 0.,
+            transmit_ms: 0.,
+            jitter_ms: 0.,
+        }
+    }
+}
+
+static STATS: RwLock<FrameStats> = RwLock::new(FrameStats::new());
+
+fn smooth(average: f32, value: f32) -> f32 {
+    if average == 0. {
+        value
+    } else {
+        average + (value - average) * SMOOTHING
+    }
+}
+
+pub fn stats() -> FrameStats {
+    *STATS.read().unwrap()
+}
+
+/// Records the time between two frames being sent, compared to `period`
+pub fn record_frame(delta: Duration, period: Duration) {
+    let mut stats = STATS.write().unwrap();
+    let delta = delta.as_secs_f32();
+    let period = period.as_secs_f32();
+
+    stats.target_fps = 1. / period;
+    if delta > 0. {
+        stats.fps = smooth(stats.fps, 1. / delta);
+    }
+    stats.jitter_ms = smooth(stats.jitter_ms, (delta - period).abs() * 1000.);
+}
+
+pub fn record_render(duration: Duration) {
+    let mut stats = STATS.write().unwrap();
+    stats.render_ms = smooth(stats.render_ms, duration.as_secs_f32() * 1000.);
+}
+
+pub fn record_transmit(duration: Duration) {
+    let mut stats = STATS.write().unwrap();
+    stats.transmit_ms = smooth(stats.transmit_ms, duration.as_secs_f32() * 1000.);
+}
@@ -0,0 +1,109 @@
+use std::sync::RwLock;
+use std::time::Instant;
+
+pub const MIN_BPM: f32 = 20.;
+pub const MAX_BPM: f32 = 300.;
+
+const MAX_TAPS: usize = 8;
+const TAP_TIMEOUT: f32 = 2.;
+
+/// A musical clock shared by every effect. It is advanced by the same delta as
+/// the effects, so anything derived from it stays in step with the animation
+pub struct TempoClock {
+    bpm: f32,
+    beats_per_bar: u32,
+    beat: f64,
+    taps: Vec<Instant>,
+}
+
+impl TempoClock {
+    const fn new() -> TempoClock {
+        TempoClock {
+            bpm: 120.,
+            beats_per_bar: 4,
+            beat: 0.,
+            taps: Vec::new(),
+        }
+    }
+
+    fn advance(&mut self, delta: f32) {
+        self.beat += (delta * self.bpm / 60.) as f64;
+    }
+
+    fn tap(&mut self, now: Instant) {
+        if let Some(last) = self.taps.last() {
+            if now.duration_since(*last).as_secs_f32() > TAP_TIMEOUT {
+                self.taps.clear();
+            }
+        }
+
+        self.taps.push(now);
+        if self.taps.len() > MAX_TAPS {
+            self.taps.remove(0);
+        }
+
+        if self.taps.len() >= 2 {
+            let first = self.taps[0];
+            let last = self.taps[self.taps.len() - 1];
+            let interval = last.duration_since(first).as_secs_f32() / (self.taps.len() - 1) as f32;
+            self.bpm = (60. / interval).clamp(MIN_BPM, MAX_BPM);
+        }
+
+        // Each tap lands on a beat, so snap the phase to it
+        self.beat = self.beat.round();
+    }
+}
+
+static CLOCK: RwLock<TempoClock> = RwLock::new(TempoClock::new());
+
+pub fn advance(delta: f32) {
+    CLOCK.write().unwrap().advance(delta);
+}
+
+pub fn tap() {
+    CLOCK.write().unwrap().tap(Instant::now());
+}
+
+pub fn bpm() -> f32 {
+    CLOCK.read().unwrap().bpm
+}
+
+pub fn set_bpm(bpm: f32) {
+    CLOCK.write().unwrap().bpm = bpm.clamp(MIN_BPM, MAX_BPM);
+}
+
+pub fn beats_per_bar() -> u32 {
+    CLOCK.read().unwrap().beats_per_bar
+}
+
+pub fn set_beats_per_bar(beats_per_bar: u32) {
+    CLOCK.write().unwrap().beats_per_bar = beats_per_bar.clamp(1, 16);
+}
+
+/// Total beats since the clock started
+pub fn beat() -> f64 {
+    CLOCK.read().unwrap().beat
+}
+
+/// How far through the current beat we are, 0 - 1
+pub fn phase() -> f32 {
+    beat().fract() as f32
+}
+
+/// The current bar and the beat within it, both counting from 0
+pub fn bar_position() -> (u64, u32) {
+    let clock = CLOCK.read().unwrap();
+    let beat = clock.beat.floor() as u64;
+    let beats_per_bar = clock.beats_per_bar as u64;
+    (beat / beats_per_bar, (beat % beats_per_bar) as u32)
+}
+
+/// How far through a cycle lasting `beats_per_cycle` beats we are, 0 - 1
+pub fn cycle_phase(beats_per_cycle: f32) -> f32 {
+    (beat() / beats_per_cycle as f64).fract() as f32
+}
+
+/// The speed needed to cover `distance` once every `beats_per_cycle` beats
+pub fn speed_for(distance: f32, beats_per_cycle: f32) -> f32 {
+    distance * bpm() / 60. / beats_per_cycle
+}
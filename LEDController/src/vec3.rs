@@ -24,6 +24,10 @@ impl Vec3 {
         f32::sqrt(Vec3::mag_squared(a))
     }
 
+    pub fn distance(a: Vec3, b: Vec3) -> f32 {
+        Vec3::mag(Vec3::sub(a, b))
+    }
+
     pub fn norm(a: Vec3) -> Vec3 {
         let norm = 1. / Vec3::mag(a);
         Vec3 {